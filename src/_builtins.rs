@@ -5,7 +5,11 @@ DON'T CHANGE THIS FILE MANUALLY, IT WILL GO AWAY!!!
 */
 use crate::builtin::Builtin;
 
-pub static BUILTINS: [Builtin; 39] = [
+pub static BUILTINS: [Builtin; 47] = [
+    Builtin {
+        name: "Balanced",
+        func: crate::value::token::tokay_token_balanced,
+    },
     Builtin {
         name: "Float",
         func: crate::value::token::tokay_token_float,
@@ -18,6 +22,18 @@ pub static BUILTINS: [Builtin; 39] = [
         name: "Int",
         func: crate::value::token::tokay_token_int,
     },
+    Builtin {
+        name: "Lines",
+        func: crate::value::token::tokay_token_lines,
+    },
+    Builtin {
+        name: "String",
+        func: crate::value::token::tokay_token_string,
+    },
+    Builtin {
+        name: "Until",
+        func: crate::value::token::tokay_token_until,
+    },
     Builtin {
         name: "Word",
         func: crate::value::token::tokay_token_word,
@@ -54,6 +70,10 @@ pub static BUILTINS: [Builtin; 39] = [
         name: "error",
         func: crate::error::tokay_function_error,
     },
+    Builtin {
+        name: "expected",
+        func: crate::builtin::tokay_function_expected,
+    },
     Builtin {
         name: "float",
         func: crate::value::value::Value::tokay_method_float,
@@ -74,6 +94,14 @@ pub static BUILTINS: [Builtin; 39] = [
         name: "int",
         func: crate::value::value::Value::tokay_method_int,
     },
+    Builtin {
+        name: "iter",
+        func: crate::value::iter::Iter::tokay_method_iter,
+    },
+    Builtin {
+        name: "iter_next",
+        func: crate::value::iter::Iter::tokay_method_iter_next,
+    },
     Builtin {
         name: "list",
         func: crate::value::list::List::tokay_method_list,
@@ -106,6 +134,10 @@ pub static BUILTINS: [Builtin; 39] = [
         name: "print",
         func: crate::builtin::tokay_function_print,
     },
+    Builtin {
+        name: "range",
+        func: crate::value::iter::tokay_function_range,
+    },
     Builtin {
         name: "repr",
         func: crate::builtin::tokay_function_repr,
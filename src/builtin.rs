@@ -2,6 +2,7 @@
 use crate::_builtins::BUILTINS;
 use crate::value;
 use crate::value::{Dict, Object, RefValue, Value};
+use crate::vm::Failure;
 use crate::{Accept, Context, Reject};
 extern crate self as tokay;
 use std::io::{self, Write};
@@ -92,7 +93,21 @@ impl Object for BuiltinRef {
         nargs: Option<Dict>,
     ) -> Result<Accept, Reject> {
         let args = context.drain(args);
-        (self.0.func)(Some(context), args, nargs)
+
+        if !self.is_consuming() {
+            return (self.0.func)(Some(context), args, nargs);
+        }
+
+        let start = context.runtime.reader.tell();
+        let result = (self.0.func)(Some(context), args, nargs);
+
+        if let Err(Reject::Next) = result {
+            context
+                .runtime
+                .reject_at(&start, || self.0.name.to_string());
+        }
+
+        result
     }
 }
 
@@ -139,6 +154,64 @@ fn test_chr() {
     );
 }
 
+tokay_function!("expected()", {
+    let context = context.unwrap();
+    let offset = context.runtime.reader.tell();
+
+    // Report the farthest failure, unless input was already consumed beyond it
+    let failure = match &context.runtime.failure {
+        Some(failure) if failure.offset.offset >= offset.offset => failure.clone(),
+        _ => Failure {
+            offset,
+            expected: Vec::new(),
+        },
+    };
+
+    failure.into_error().into()
+});
+
+#[test]
+fn test_expected() {
+    assert_eq!(
+        crate::run(
+            "Item : @{ Int | Word }\n'(' Item (',' Item)* ')' | expected()",
+            "(1,a,2 3)"
+        ),
+        Err("Line 1, column 7: Expecting one of: ',', ')'".to_string())
+    );
+
+    // Without expected(), input which isn't matched is skipped
+    assert_eq!(
+        crate::run(
+            "Item : @{ Int | Word }\nList : @{ '(' Item (',' Item)* ')' }\nList",
+            "(1,a)(1,a,2 3)"
+        ),
+        Ok(Some(crate::value!([1, "a"])))
+    );
+
+    assert_eq!(
+        crate::run("Pair : @{ Word '=' Int }\nPair", "a=1 b=x c=3"),
+        Ok(Some(crate::value!([["a", 1], ["c", 3]])))
+    );
+
+    assert_eq!(
+        crate::run(
+            "Kv : @{ Word _ '=' _ Int }\nKv",
+            "hello world, x = 1, y = 2"
+        ),
+        Ok(Some(crate::value!([["x", 1], ["y", 2]])))
+    );
+
+    // Named parselets are expected instead of their tokens
+    assert_eq!(
+        crate::run(
+            "Item : @{ Int | Word }\nList : @{ '(' Item (',' Item)* ')' }\nList | expected()",
+            "(1,)"
+        ),
+        Err("Line 1, column 4: Expecting Item".to_string())
+    );
+}
+
 tokay_function!("ord(c)", {
     let c = c.to_string();
     if c.chars().count() != 1 {
@@ -1,5 +1,7 @@
 //! Compiler's internal Abstract Syntax Tree traversal
+use std::cell::RefCell;
 use std::collections::HashSet;
+use std::rc::Rc;
 use tokay_macros::tokay_function;
 extern crate self as tokay;
 use super::*;
@@ -7,16 +9,22 @@ use crate::error::Error;
 use crate::reader::Offset;
 use crate::utils;
 use crate::value;
+use crate::value::token::{self, Regex};
 use crate::value::{Dict, List, Object, RefValue, Str, Token};
 use crate::vm::*;
 use charclass::CharClass;
+use regex_syntax::hir::{ClassUnicode, ClassUnicodeRange};
+
+// Maximum nesting depth of instances of a generic parselet created by its own instances
+const MAX_GENERIC_DEPTH: usize = 8;
 
 /// Checks whether identifier's name is the name of a reserved word.
 fn identifier_is_valid(ident: &str) -> Result<(), Error> {
     match ident {
-        "accept" | "begin" | "break" | "continue" | "else" | "end" | "exit" | "expect"
-        | "false" | "for" | "if" | "in" | "loop" | "next" | "not" | "null" | "peek" | "push"
-        | "reject" | "repeat" | "return" | "true" | "void" => Err(Error::new(
+        "accept" | "begin" | "break" | "catch" | "continue" | "cut" | "else" | "end" | "exit"
+        | "expect" | "false" | "for" | "if" | "import" | "in" | "loop" | "next" | "not"
+        | "null" | "peek" | "push" | "recover" | "reject" | "repeat" | "return" | "true"
+        | "try" | "void" => Err(Error::new(
             None,
             format!("Expected identifier, found reserved word '{}'", ident),
         )),
@@ -62,8 +70,16 @@ fn traverse_node_offset(node: &Dict) -> Option<Offset> {
         .get("col")
         .and_then(|col| Some(col.to_usize().unwrap() as u32));
 
+    let source = node.get("source").map(|source| source.to_string().into());
+
     if let (Some(offset), Some(row), Some(col)) = (offset, row, col) {
-        Some(Offset { offset, row, col })
+        Some(Offset {
+            source,
+            offset,
+            row,
+            col,
+            indent: None,
+        })
     } else {
         None
     }
@@ -76,6 +92,118 @@ fn insert_offset(ops: &mut Vec<ImlOp>, node: &Dict) {
     }
 }
 
+/// Negates a byte class within the range of bytes.
+fn negate_bytes(ccl: &CharClass) -> CharClass {
+    let mut neg = CharClass::new();
+
+    for ch in '\0'..='\u{ff}' {
+        if !ccl.test(&(ch..=ch)) {
+            neg.add(ch..=ch);
+        }
+    }
+
+    neg
+}
+
+/// Returns the character-class of a ccl node, and whether it shall be negated.
+///
+/// Items are united into one class, until the operators `&&` (intersection) or `--` (difference)
+/// combine the class collected so far with the items that follow, from left to right.
+fn traverse_node_ccl(
+    compiler: &mut Compiler,
+    node: &Dict,
+    ignore_case: bool,
+) -> (ClassUnicode, bool) {
+    let offset = traverse_node_offset(node);
+
+    let node = node["children"].borrow();
+    let node = node.object::<Dict>().unwrap();
+
+    let emit = node["emit"].borrow();
+    let emit = emit.object::<Str>().unwrap().as_str();
+
+    let children = List::from(&node["children"]);
+
+    let mut class = ClassUnicode::empty();
+    let mut operand = ClassUnicode::empty();
+    let mut operator = None;
+
+    // Applies the pending operator on the class with the collected operand.
+    // Case-insensitive classes are extended by the case variants of all characters.
+    let apply = |class: &mut ClassUnicode, operand: &mut ClassUnicode, operator: Option<&str>| {
+        if ignore_case {
+            operand.case_fold_simple();
+        }
+
+        match operator {
+            Some("intersect") => class.intersect(operand),
+            Some("difference") => class.difference(operand),
+            _ => class.union(operand),
+        }
+
+        *operand = ClassUnicode::empty();
+    };
+
+    for range in children.iter() {
+        let range = range.borrow();
+        let range = range.object::<Dict>().unwrap();
+
+        let emit = range["emit"].borrow();
+        let emit = emit.object::<Str>().unwrap().as_str();
+
+        match &emit[..] {
+            "char" => {
+                let value = range["value"].to_string();
+                let ch = value.chars().next().unwrap();
+                operand.push(ClassUnicodeRange::new(ch, ch));
+            }
+            "range" => {
+                let value = range["value"].to_string();
+                let from = value.chars().nth(0).unwrap();
+                let to = value.chars().nth(1).unwrap();
+
+                operand.push(ClassUnicodeRange::new(from, to));
+            }
+            "property" | "property_neg" => {
+                let name = range["value"].to_string();
+
+                if let Some(mut property) = token::unicode_property(&name) {
+                    if emit == "property_neg" {
+                        property.negate();
+                    }
+
+                    operand.union(&property);
+                } else {
+                    compiler.errors.push(Error::new(
+                        offset.clone(),
+                        format!("Unknown Unicode property '{}'", name),
+                    ));
+                }
+            }
+            "intersect" => {
+                apply(&mut class, &mut operand, operator);
+                operator = Some("intersect");
+            }
+            "difference" => {
+                apply(&mut class, &mut operand, operator);
+                operator = Some("difference");
+            }
+            _ => {
+                unreachable!();
+            }
+        }
+    }
+
+    apply(&mut class, &mut operand, operator);
+
+    if emit == "ccl_neg" {
+        (class, true)
+    } else {
+        assert!(emit == "ccl");
+        (class, false)
+    }
+}
+
 // Traverse a value node into an ImlValue instance
 fn traverse_node_value(compiler: &mut Compiler, node: &Dict) -> ImlValue {
     let emit = node["emit"].borrow();
@@ -93,7 +221,10 @@ fn traverse_node_value(compiler: &mut Compiler, node: &Dict) -> ImlValue {
         "value_void" => value!(void).into(),
 
         // Tokens
-        "value_token_match" | "value_token_touch" => {
+        "value_token_match"
+        | "value_token_touch"
+        | "value_token_match_icase"
+        | "value_token_touch_icase" => {
             let mut value = node["value"].to_string();
 
             if value.len() == 0 {
@@ -104,77 +235,169 @@ fn traverse_node_value(compiler: &mut Compiler, node: &Dict) -> ImlValue {
                 value = "#INVALID".to_string();
             }
 
-            if emit == "value_token_match" {
-                RefValue::from(Token::Match(value)).into()
-            } else {
-                RefValue::from(Token::Touch(value)).into()
+            match emit {
+                "value_token_match" => RefValue::from(Token::Match(value)).into(),
+                "value_token_touch" => RefValue::from(Token::Touch(value)).into(),
+                "value_token_match_icase" => RefValue::from(Token::MatchIgnoreCase(value)).into(),
+                _ => RefValue::from(Token::TouchIgnoreCase(value)).into(),
             }
         }
         "value_token_any" => RefValue::from(Token::any()).into(),
-        "value_token_ccl" => {
-            let node = node["children"].borrow();
-            let node = node.object::<Dict>().unwrap();
+        "value_token_ccl" | "value_token_ccl_icase" => {
+            let (mut class, negate) =
+                traverse_node_ccl(compiler, node, emit == "value_token_ccl_icase");
 
-            let emit = node["emit"].borrow();
-            let emit = emit.object::<Str>().unwrap().as_str();
-
-            let children = List::from(&node["children"]);
-
-            let mut ccl = CharClass::new();
-
-            for range in children.iter() {
-                let range = range.borrow();
-                let range = range.object::<Dict>().unwrap();
+            if negate {
+                class.negate();
+            }
 
-                let emit = range["emit"].borrow();
-                let emit = emit.object::<Str>().unwrap().as_str();
+            RefValue::from(Token::Char(token::class_to_ccl(&class))).into()
+        }
+        "value_token_bytes" => {
+            let value = node["value"].to_string();
 
-                let value = range["value"].borrow();
-                let value = value.object::<Str>().unwrap().as_str();
+            if value.is_empty() {
+                compiler.errors.push(Error::new(
+                    traverse_node_offset(node),
+                    "Empty match not allowed".to_string(),
+                ));
+            } else if value.chars().any(|ch| ch > '\u{ff}') {
+                compiler.errors.push(Error::new(
+                    traverse_node_offset(node),
+                    "Byte literal may only contain characters up to '\\xff'".to_string(),
+                ));
+            }
 
-                match &emit[..] {
-                    "char" => {
-                        let ch = value.chars().next().unwrap();
-                        ccl.add(ch..=ch);
-                    }
-                    "range" => {
-                        let from = value.chars().nth(0).unwrap();
-                        let to = value.chars().nth(1).unwrap();
+            RefValue::from(Token::ByteMatch(value.chars().map(|ch| ch as u8).collect())).into()
+        }
+        "value_token_byteclass" => {
+            let (class, negate) = traverse_node_ccl(compiler, node, false);
+            let ccl = token::class_to_ccl(&class);
 
-                        ccl.add(from..=to);
-                    }
-                    _ => {
-                        unreachable!();
-                    }
-                }
+            if class.iter().any(|range| range.end() > '\u{ff}') {
+                compiler.errors.push(Error::new(
+                    traverse_node_offset(node),
+                    "Byte class may only contain characters up to '\\xff'".to_string(),
+                ));
             }
 
-            if emit == "ccl_neg" {
-                RefValue::from(Token::Char(ccl.negate())).into()
+            if negate {
+                RefValue::from(Token::Byte(negate_bytes(&ccl))).into()
             } else {
-                assert!(emit == "ccl");
-                RefValue::from(Token::Char(ccl)).into()
+                RefValue::from(Token::Byte(ccl)).into()
             }
         }
 
+        "value_token_regex" => {
+            let value = node["value"].to_string();
+
+            RefValue::from(Token::Regex(Box::new(match Regex::new(&value) {
+                Ok(regex) => regex,
+                Err(err) => {
+                    compiler.errors.push(Error::new(
+                        traverse_node_offset(node),
+                        format!("Invalid regular expression: {}", err),
+                    ));
+
+                    Regex::new("#INVALID").unwrap()
+                }
+            })))
+            .into()
+        }
+
         // Parselets
         "value_parselet" => {
+            let mut children = List::from(&node["children"]);
+
+            // Generic parselets become templates, which are instantiated on demand
+            let generics = children[0]
+                .borrow()
+                .object::<Dict>()
+                .filter(|first| first["emit"].to_string() == "generics")
+                .map(|generics| List::from(&generics["children"]));
+
+            if let Some(generics) = generics {
+                children.remove(0);
+
+                let mut params: Vec<(String, Option<ImlValue>)> = Vec::new();
+
+                for gen in generics.iter() {
+                    let gen = gen.borrow();
+                    let gen = gen.object::<Dict>().unwrap();
+
+                    let children = List::from(&gen["children"]);
+                    let ident = children[0].borrow().object::<Dict>().unwrap()["value"].to_string();
+
+                    if params.iter().any(|(name, _)| *name == ident) {
+                        compiler.errors.push(Error::new(
+                            traverse_node_offset(gen),
+                            format!("Generic '{}' already given in signature before", ident),
+                        ));
+
+                        continue;
+                    }
+
+                    let default = if children.len() == 2 {
+                        let default = children[1].borrow();
+                        Some(traverse_node_static(
+                            compiler,
+                            None,
+                            default.object::<Dict>().unwrap(),
+                        ))
+                    } else {
+                        None
+                    };
+
+                    params.push((ident, default));
+                }
+
+                let mut node = node.clone();
+                node.insert("children".to_string(), RefValue::from(children));
+
+                return ImlTemplate::new(params, RefValue::from(node)).into();
+            }
+
             compiler.push_parselet();
+            let mut memo = None;
+
+            // Annotations
+            let first = children[0].clone();
+            let first = first.borrow();
+
+            if let Some(annotations) = first.object::<Dict>() {
+                if children.len() > 1 && annotations["emit"].to_string() == "annotations" {
+                    for annotation in List::from(&annotations["children"]).iter() {
+                        let annotation = annotation.borrow();
+                        let annotation = annotation.object::<Dict>().unwrap();
+
+                        match annotation["value"].to_string().as_str() {
+                            "memo" => memo = Some(true),
+                            "nomemo" => memo = Some(false),
+                            name => compiler.errors.push(Error::new(
+                                traverse_node_offset(annotation),
+                                format!("Unknown parselet annotation '{}'", name),
+                            )),
+                        }
+                    }
 
-            let children = node["children"].borrow();
+                    children.remove(0);
+                }
+            }
 
-            let (args, body) = if let Some(children) = children.object::<List>() {
-                assert!(children.len() == 2);
-                (Some(&children[0]), children[1].borrow())
-            } else {
-                (None, children)
-            };
+            drop(first);
+
+            assert!(children.len() <= 2);
+            let body = children.pop().unwrap();
+            let body = body.borrow();
+            let args = children.pop();
 
             // Create signature
             let mut sig: Vec<(String, Option<usize>)> = Vec::new();
             let mut sig_names = HashSet::new();
+            let mut args_catchall = None;
+            let mut nargs_catchall = None;
 
-            if let Some(args) = args {
+            if let Some(args) = &args {
                 for node in List::from(args).iter() {
                     let node = node.borrow();
                     let node = node.object::<Dict>().unwrap();
@@ -205,6 +428,50 @@ fn traverse_node_value(compiler: &mut Compiler, node: &Dict) -> ImlValue {
                         sig_names.insert(ident.clone());
                     }
 
+                    // Catch-all arguments are specified behind any other arguments, *args before **nargs
+                    match node["emit"].to_string().as_str() {
+                        catchall @ ("arg_args" | "arg_nargs") => {
+                            let prefix = if catchall == "arg_args" { "*" } else { "**" };
+
+                            if nargs_catchall.is_some()
+                                || (catchall == "arg_args" && args_catchall.is_some())
+                            {
+                                compiler.errors.push(Error::new(
+                                    traverse_node_offset(node),
+                                    format!(
+                                        "Catch-all argument '{}{}' not allowed here; Use '*args' and '**nargs' only once, in this order",
+                                        prefix, ident
+                                    ),
+                                ));
+
+                                continue;
+                            }
+
+                            let addr = Some(compiler.new_local(&ident));
+
+                            if catchall == "arg_args" {
+                                args_catchall = addr;
+                            } else {
+                                nargs_catchall = addr;
+                            }
+
+                            continue;
+                        }
+                        _ => {
+                            if args_catchall.is_some() || nargs_catchall.is_some() {
+                                compiler.errors.push(Error::new(
+                                    traverse_node_offset(node),
+                                    format!(
+                                        "Argument '{}' must be specified before any catch-all argument",
+                                        ident
+                                    ),
+                                ));
+
+                                continue;
+                            }
+                        }
+                    }
+
                     compiler.new_local(&ident);
 
                     assert!(children.len() <= 2);
@@ -231,7 +498,11 @@ fn traverse_node_value(compiler: &mut Compiler, node: &Dict) -> ImlValue {
             let body = traverse_node(compiler, &body.object::<Dict>().unwrap());
             let body = ImlOp::from_vec(body.into_ops(compiler, true));
 
-            compiler.pop_parselet(None, sig, body).into()
+            let mut parselet = compiler.pop_parselet(None, sig, body);
+            parselet.memo = memo;
+            parselet.args_catchall = args_catchall;
+            parselet.nargs_catchall = nargs_catchall;
+            parselet.into()
         }
         _ => unimplemented!("unhandled value node {}", emit),
     }
@@ -255,9 +526,14 @@ fn traverse_node_static(compiler: &mut Compiler, lvalue: Option<&str>, node: &Di
             compiler.pop_parselet(None, Vec::new(), ImlOp::from(Op::Nop));
 
             if let Some(lvalue) = lvalue {
-                if let ImlValue::Parselet(parselet) = &value {
-                    let mut parselet = parselet.borrow_mut();
-                    parselet.name = Some(lvalue.to_string());
+                match &value {
+                    ImlValue::Parselet(parselet) => {
+                        parselet.borrow_mut().name = Some(lvalue.to_string());
+                    }
+                    ImlValue::Template(template) => {
+                        template.borrow_mut().name = Some(lvalue.to_string());
+                    }
+                    ImlValue::Value(_) => {}
                 }
             }
 
@@ -280,6 +556,224 @@ fn traverse_node_static(compiler: &mut Compiler, lvalue: Option<&str>, node: &Di
     }
 }
 
+/** Traverse a generic argument.
+
+Constants are used directly, any other argument becomes a static value or parselet. */
+fn traverse_node_generic_arg(compiler: &mut Compiler, node: &Dict) -> ImlValue {
+    if node["emit"].to_string() == "call" {
+        let callee = node["children"].borrow();
+
+        if let Some(callee) = callee.object::<Dict>() {
+            if callee["emit"].to_string() == "identifier" {
+                match compiler.get_constant(&callee["value"].to_string()) {
+                    Some(ImlValue::Template(_)) | None => {}
+                    Some(value) => return value,
+                }
+            }
+        }
+    }
+
+    traverse_node_static(compiler, None, node)
+}
+
+/** Instantiate a generic parselet with the given generic arguments.
+
+The parselet is traversed again in the scope of its definition, with the generic parameters defined
+as constants. Every distinct set of generic arguments results in its own parselet. */
+fn traverse_generic_instance(
+    compiler: &mut Compiler,
+    template: &Rc<RefCell<ImlTemplate>>,
+    mut args: Vec<ImlValue>,
+    offset: Option<Offset>,
+    instance: Option<Rc<RefCell<ImlParselet>>>,
+) -> Option<ImlValue> {
+    let (name, generics, node, module) = {
+        let template = template.borrow();
+
+        (
+            template
+                .name
+                .clone()
+                .unwrap_or("<unnamed generic parselet>".to_string()),
+            template.generics.clone(),
+            template.node.clone(),
+            template.module.clone(),
+        )
+    };
+
+    if args.len() > generics.len() {
+        compiler.errors.push(Error::new(
+            offset,
+            format!(
+                "Too many generic arguments for '{}', expecting {} at most",
+                name,
+                generics.len()
+            ),
+        ));
+
+        return None;
+    }
+
+    for (param, default) in &generics[args.len()..] {
+        if let Some(default) = default {
+            args.push(default.clone());
+        } else {
+            compiler.errors.push(Error::new(
+                offset,
+                format!("Missing generic argument '{}' for '{}'", param, name),
+            ));
+
+            return None;
+        }
+    }
+
+    // Consumable generics must be consuming
+    for ((param, _), arg) in generics.iter().zip(&args) {
+        if utils::identifier_is_consumable(param) {
+            if let ImlValue::Value(value) = arg {
+                if !value.is_consuming() {
+                    compiler.errors.push(Error::new(
+                        offset,
+                        format!(
+                            "Generic '{}' of '{}' must be consumable, but {} is not",
+                            param,
+                            name,
+                            value.repr()
+                        ),
+                    ));
+
+                    return None;
+                }
+            }
+        }
+    }
+
+    // Register the instance before its traversal, so that it can be used recursively
+    let instance = match instance {
+        Some(instance) => instance,
+        None => {
+            if let Some(instance) = template.borrow().instance(&args) {
+                return Some(instance);
+            }
+
+            traverse_generic_placeholder(&name, &args)
+        }
+    };
+
+    // Instances creating further instances of their template with growing arguments never end
+    if compiler
+        .templates
+        .iter()
+        .filter(|known| Rc::ptr_eq(known, template))
+        .count()
+        == MAX_GENERIC_DEPTH
+    {
+        compiler.errors.push(Error::new(
+            offset,
+            format!(
+                "Generic instantiation too deep, '{}' exceeds {} nested instances",
+                name, MAX_GENERIC_DEPTH
+            ),
+        ));
+
+        return None;
+    }
+
+    if template.borrow().instance(&args).is_none() {
+        template
+            .borrow_mut()
+            .instances
+            .push((args.clone(), ImlValue::Parselet(instance.clone())));
+    }
+
+    let name = instance.borrow().name.clone();
+
+    // Switch to the scopes where the template was defined
+    let position = compiler.scopes.iter().position(|scope| match scope {
+        Scope::Parselet { constants, .. } | Scope::Block { constants, .. } => constants
+            .values()
+            .any(|value| matches!(value, ImlValue::Template(known) if Rc::ptr_eq(known, template))),
+        Scope::Loop => false,
+    });
+
+    let usage_start = compiler.usages.len();
+    let mut outer = None;
+    let mut inner = Vec::new();
+
+    if let Some(position) = position {
+        inner = compiler.scopes.drain(..position).collect();
+    } else if let Some(module) = module {
+        outer = Some((
+            std::mem::take(&mut compiler.scopes),
+            std::mem::replace(&mut compiler.namespaces, module.namespaces),
+        ));
+
+        compiler.push_parselet();
+
+        if let Scope::Parselet { constants, .. } = &mut compiler.scopes[0] {
+            *constants = module.constants;
+        }
+    }
+
+    compiler.push_block();
+
+    for ((param, _), arg) in generics.iter().zip(args) {
+        compiler.set_constant(param, arg);
+    }
+
+    compiler.templates.push(template.clone());
+    let value = traverse_node_value(compiler, node.borrow().object::<Dict>().unwrap());
+    compiler.templates.pop();
+
+    compiler.pop_block();
+
+    if let Some((scopes, namespaces)) = outer {
+        compiler.dispose_usages(usage_start);
+        compiler.scopes = scopes;
+        compiler.namespaces = namespaces;
+    } else {
+        compiler.scopes.splice(0..0, inner);
+    }
+
+    // Move the traversed parselet into the registered instance
+    if let ImlValue::Parselet(parselet) = value {
+        *instance.borrow_mut() = Rc::try_unwrap(parselet).unwrap().into_inner();
+        instance.borrow_mut().name = name;
+    }
+
+    Some(ImlValue::Parselet(instance))
+}
+
+/** Create the parselet for an instance of a generic parselet, before it is traversed.
+
+It is consuming according to the name of the generic parselet, until its traversal is done. */
+fn traverse_generic_placeholder(name: &str, args: &[ImlValue]) -> Rc<RefCell<ImlParselet>> {
+    let mut parselet = ImlParselet::new(
+        Some(format!(
+            "{}<{}>",
+            name,
+            args.iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+        Vec::new(),
+        0,
+        ImlOp::Nop,
+        ImlOp::Nop,
+        ImlOp::Nop,
+    );
+
+    if utils::identifier_is_consumable(name) {
+        parselet.consuming = Some(Consumable {
+            leftrec: false,
+            nullable: false,
+        });
+    }
+
+    Rc::new(RefCell::new(parselet))
+}
+
 // Traverse lvalue
 fn traverse_node_lvalue(
     compiler: &mut Compiler,
@@ -363,6 +857,16 @@ fn traverse_node_lvalue(
                     break;
                 }
 
+                // Check for not assigning into a namespace
+                if name.contains("::") {
+                    compiler.errors.push(Error::new(
+                        traverse_node_offset(node),
+                        format!("Cannot assign to '{}' of an imported namespace", name),
+                    ));
+
+                    break;
+                }
+
                 // Check if identifier is valid
                 if let Err(mut error) = identifier_is_valid(name) {
                     if let Some(offset) = traverse_node_offset(node) {
@@ -421,6 +925,15 @@ fn traverse_node_lvalue(
                     } else {
                         ops.push(Op::LoadGlobal(addr).into())
                     }
+                } else if compiler.module && compiler.is_global_scope() {
+                    // Imported modules don't have any storage for global variables
+                    compiler.errors.push(Error::new(
+                        traverse_node_offset(node),
+                        format!(
+                            "Cannot define global variable '{}' inside of a module; Use a constant instead",
+                            name
+                        ),
+                    ));
                 } else {
                     let addr = compiler.new_local(name);
                     if store {
@@ -660,6 +1173,22 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
                     }
                     .resolve_or_dispose(compiler),
                 )
+            } else if let ImlResult::Value(value @ ImlValue::Parselet(_)) = callee {
+                // Parselets, e.g. instances of generic parselets, are called statically
+                if value.is_consuming() {
+                    compiler.mark_consuming();
+                }
+
+                insert_offset(&mut ops, node);
+                let addr = compiler.define_value(value);
+
+                if args == 0 && nargs == 0 {
+                    ops.push(Op::CallStatic(addr).into());
+                } else if args > 0 && nargs == 0 {
+                    ops.push(Op::CallStaticArg(Box::new((addr, args))).into());
+                } else {
+                    ops.push(Op::CallStaticArgNamed(Box::new((addr, args))).into());
+                }
             } else {
                 ops.extend(callee.into_ops(compiler, false));
 
@@ -711,13 +1240,27 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
                 return ImlResult::Empty;
             }
 
+            if ident.contains("::") {
+                compiler.errors.push(Error::new(
+                    traverse_node_offset(node),
+                    format!(
+                        "Cannot define constant '{}' in an imported namespace",
+                        ident
+                    ),
+                ));
+
+                return ImlResult::Empty;
+            }
+
             // Distinguish between pure values or an expression
             let value = value.object::<Dict>().unwrap();
 
             // fixme: Restricted to pure values currently.
             let value = traverse_node_static(compiler, Some(&ident), value);
 
-            if value.is_consuming() {
+            if matches!(value, ImlValue::Template(_)) {
+                // Generic parselets are consuming depending on their instances
+            } else if value.is_consuming() {
                 if !utils::identifier_is_consumable(ident) {
                     compiler.errors.push(Error::new(
                         traverse_node_offset(node),
@@ -750,7 +1293,21 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
             }
 
             //println!("{} : {:?}", ident, value);
-            compiler.set_constant(ident, value);
+            compiler.set_constant(ident, value.clone());
+
+            // Instantiate generic parselets which have been requested before
+            if let ImlValue::Template(template) = &value {
+                let mut i = 0;
+
+                while i < compiler.instances.len() {
+                    if compiler.instances[i].0 == ident {
+                        let (_, args, instance, offset) = compiler.instances.remove(i);
+                        traverse_generic_instance(compiler, template, args, offset, Some(instance));
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
 
             // Try to resolve usage of newly introduced constant in current scope
             compiler.resolve();
@@ -758,6 +1315,72 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
             ImlResult::Empty
         }
 
+        // generic --------------------------------------------------------
+        "generic" => {
+            let children = List::from(&node["children"]);
+
+            let name = children[0].borrow().object::<Dict>().unwrap()["value"].to_string();
+
+            // Generic arguments are evaluated where the instance is requested
+            let mut args = Vec::new();
+
+            for arg in children.iter().skip(1) {
+                for arg in List::from(arg).iter() {
+                    let arg = arg.borrow();
+                    args.push(traverse_node_generic_arg(
+                        compiler,
+                        arg.object::<Dict>().unwrap(),
+                    ));
+                }
+            }
+
+            match compiler.get_constant(&name) {
+                Some(ImlValue::Template(template)) => {
+                    match traverse_generic_instance(
+                        compiler,
+                        &template,
+                        args,
+                        traverse_node_offset(node),
+                        None,
+                    ) {
+                        Some(instance) => ImlResult::Value(instance),
+                        None => ImlResult::Empty,
+                    }
+                }
+                Some(_) => {
+                    compiler.errors.push(Error::new(
+                        traverse_node_offset(node),
+                        format!("'{}' is not a generic parselet", name),
+                    ));
+
+                    ImlResult::Empty
+                }
+                None => {
+                    // Generic parselets defined later on are instantiated with their definition
+                    let instance = compiler
+                        .instances
+                        .iter()
+                        .find(|(known, known_args, ..)| *known == name && *known_args == args)
+                        .map(|(.., instance, _)| instance.clone());
+
+                    let instance = instance.unwrap_or_else(|| {
+                        let instance = traverse_generic_placeholder(&name, &args);
+
+                        compiler.instances.push((
+                            name,
+                            args,
+                            instance.clone(),
+                            traverse_node_offset(node),
+                        ));
+
+                        instance
+                    });
+
+                    ImlResult::Value(ImlValue::Parselet(instance))
+                }
+            }
+        }
+
         // identifier -----------------------------------------------------
         "identifier" => {
             let name = node["value"].borrow();
@@ -852,7 +1475,62 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
                     },
                 );
 
-                compiler.define_value(main.into());
+                // The main parselet of an imported module is never executed.
+                if !compiler.module {
+                    compiler.define_value(main.into());
+                }
+            }
+
+            ImlResult::Empty
+        }
+
+        // import ---------------------------------------------------------
+        "import" => {
+            let (filename, name) = if let Some(children) = node.get("children") {
+                let children = List::from(children);
+                let name = children[1].borrow().object::<Dict>().unwrap()["value"].to_string();
+
+                (children[0].to_string(), Some(name))
+            } else {
+                (node["value"].to_string(), None)
+            };
+
+            if compiler.scopes.len() != 1 {
+                compiler.errors.push(Error::new(
+                    traverse_node_offset(node),
+                    format!("'import' is only allowed at the top-level"),
+                ));
+
+                return ImlResult::Empty;
+            }
+
+            // Without an explicit name, the namespace is named after the file
+            let name = name.unwrap_or_else(|| {
+                std::path::Path::new(&filename)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+
+            let valid = name
+                .chars()
+                .enumerate()
+                .all(|(i, ch)| ch == '_' || ch.is_alphabetic() || (i > 0 && ch.is_numeric()));
+
+            if name.is_empty() || !valid || identifier_is_valid(&name).is_err() {
+                compiler.errors.push(Error::new(
+                    traverse_node_offset(node),
+                    format!(
+                        "Invalid namespace '{}'; Use 'import \"{}\" as name' instead",
+                        name, filename
+                    ),
+                ));
+
+                return ImlResult::Empty;
+            }
+
+            if let Some(constants) = compiler.import(&filename, traverse_node_offset(node)) {
+                compiler.namespaces.insert(name, constants);
             }
 
             ImlResult::Empty
@@ -909,6 +1587,7 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
 
                     Op::Continue.into()
                 }
+                "cut" => Op::Cut.into(),
                 "next" => Op::Next.into(),
                 "nop" => ImlOp::Nop,
                 "reject" => Op::Reject.into(),
@@ -1041,13 +1720,24 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
                             let value = value.borrow();
 
                             if let Some(token) = value.object::<Token>() {
-                                if let Token::Char(ccl) = token.clone() {
+                                // Byte classes are handled like character classes
+                                let token = match token.clone() {
+                                    Token::Char(ccl) => {
+                                        Some((Token::Chars(ccl.clone()), Token::Char(ccl.negate())))
+                                    }
+                                    Token::Byte(ccl) => Some((
+                                        Token::Bytes(ccl.clone()),
+                                        Token::Byte(negate_bytes(&ccl)),
+                                    )),
+                                    _ => None,
+                                };
+
+                                if let Some((chars, negated)) = token {
                                     match parts[2] {
                                         // mod_pos on Token::Char becomes Token::Chars
                                         "pos" | "kle" => {
-                                            let chars = ImlResult::Value(
-                                                RefValue::from(Token::Chars(ccl)).into(),
-                                            );
+                                            let chars =
+                                                ImlResult::Value(RefValue::from(chars).into());
 
                                             let mut ops =
                                                 ImlOp::from_vec(chars.into_ops(compiler, true));
@@ -1063,7 +1753,7 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
                                         // mod_not on Token::Char becomes negated Token::Char
                                         "not" => {
                                             return ImlResult::Value(
-                                                RefValue::from(Token::Char(ccl.negate())).into(),
+                                                RefValue::from(negated).into(),
                                             );
                                         }
                                         _ => {}
@@ -1111,10 +1801,18 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
                         "expect" => {
                             // Just give some helpful information here for most cases;
                             // `expect` will be replaced by the `Expect<P, msg>` generic parselet in future.
-                            let msg = if let ImlOp::Op(Op::CallStatic(i)) = &op {
-                                Some(format!("Expecting {}", compiler.values[*i]))
-                            } else {
-                                None
+                            let msg = match &op {
+                                ImlOp::Op(Op::CallStatic(i)) => {
+                                    Some(format!("Expecting {}", compiler.values[*i]))
+                                }
+                                // Calls may be preceded by their source offset
+                                ImlOp::Ops(ops) => match ops.as_slice() {
+                                    [ImlOp::Op(Op::Offset(_)), ImlOp::Op(Op::CallStatic(i))] => {
+                                        Some(format!("Expecting {}", compiler.values[*i]))
+                                    }
+                                    _ => None,
+                                },
+                                _ => None,
                             };
 
                             ImlExpect::new(op, msg)
@@ -1160,6 +1858,99 @@ fn traverse_node(compiler: &mut Compiler, node: &Dict) -> ImlResult {
                     )
                 }
 
+                "recover" => {
+                    let children = List::from(&node["children"]);
+
+                    let body =
+                        traverse_node_or_list(compiler, &children[0]).into_ops(compiler, true);
+                    let sync = if children.len() > 1 {
+                        traverse_node_or_list(compiler, &children[1]).into_ops(compiler, false)
+                    } else {
+                        vec![Op::PushVoid.into()]
+                    };
+
+                    ImlRecover::new(ImlOp::from_vec(body), ImlOp::from_vec(sync))
+                }
+
+                "try" => {
+                    let children = List::from(&node["children"]);
+
+                    let body =
+                        traverse_node_or_list(compiler, &children[0]).into_ops(compiler, true);
+
+                    // The caught error is either stored into a variable or dropped
+                    let mut catch = Vec::new();
+
+                    if children.len() == 3 {
+                        let var = children[1].borrow();
+                        let var = var.object::<Dict>().unwrap();
+
+                        catch.extend(
+                            traverse_node_lvalue(compiler, var, true, false)
+                                .into_ops(compiler, false),
+                        );
+                    } else {
+                        catch.push(Op::Drop.into());
+                    }
+
+                    catch.extend(
+                        traverse_node_or_list(compiler, children.last().unwrap())
+                            .into_ops(compiler, true),
+                    );
+
+                    ImlTry::new(ImlOp::from_vec(body), ImlOp::from_vec(catch))
+                }
+
+                "for" if parts.get(2) == Some(&"in") => {
+                    let children = List::from(&node["children"]);
+                    let (vars, iterable, body) = (
+                        &children[..children.len() - 2],
+                        &children[children.len() - 2],
+                        &children[children.len() - 1],
+                    );
+
+                    // The iterator is held by a temporary variable
+                    let temp = compiler.new_temporary();
+                    let (load, store): (ImlOp, ImlOp) =
+                        if let Some(addr) = compiler.get_local(&temp) {
+                            (Op::LoadFast(addr).into(), Op::StoreFast(addr).into())
+                        } else {
+                            let addr = compiler.get_global(&temp).unwrap();
+                            (Op::LoadGlobal(addr).into(), Op::StoreGlobal(addr).into())
+                        };
+
+                    let mut initial =
+                        traverse_node_or_list(compiler, iterable).into_ops(compiler, false);
+                    initial.push(Op::MakeIter.into());
+                    initial.push(store);
+
+                    compiler.push_loop();
+
+                    // Either the item or the key and the item are pushed, so they are stored in reverse
+                    let mut ops = Vec::new();
+
+                    for var in vars.iter().rev() {
+                        let var = var.borrow();
+                        let var = var.object::<Dict>().unwrap();
+
+                        ops.extend(
+                            traverse_node_lvalue(compiler, var, true, true)
+                                .into_ops(compiler, false),
+                        );
+                        ops.push(Op::Drop.into());
+                    }
+
+                    ops.extend(traverse_node_or_list(compiler, body).into_ops(compiler, true));
+
+                    compiler.pop_loop();
+
+                    ImlLoop::new(
+                        ImlOp::from_vec(initial),
+                        ImlOp::from_vec(vec![load, Op::IterNext(vars.len()).into()]),
+                        ImlOp::from_vec(ops),
+                    )
+                }
+
                 "for" => {
                     let children = node["children"].borrow();
                     let children = children.object::<List>().unwrap();
@@ -1382,6 +2173,10 @@ tokay_function!("ast(emit, value=void)", {
     ret.insert("row".to_string(), value!(context.reader_start.row as usize));
     ret.insert("col".to_string(), value!(context.reader_start.col as usize));
 
+    if let Some(source) = &context.reader_start.source {
+        ret.insert("source".to_string(), value!(source.as_ref()));
+    }
+
     // Store positions of reader stop
     let current = context.runtime.reader.tell();
 
@@ -1,12 +1,16 @@
 //! Tokay compiler interface
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fs::File;
 use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use super::*;
 use crate::builtin::Builtin;
 use crate::error::Error;
-use crate::reader::Reader;
-use crate::value::{RefValue, Token};
+use crate::reader::{Offset, Reader};
+use crate::value::{ParseletRef, RefValue, Token};
 use crate::vm::*;
 
 /** Compiler symbolic scope.
@@ -34,20 +38,41 @@ pub(super) enum Scope {
     Loop, // loop level (allows use of break & continue)
 }
 
+/// Instance of a generic parselet requested before its definition, with its name, generic
+/// arguments, the parselet to be instantiated into, and the offset of the request.
+pub(super) type Instance = (
+    String,
+    Vec<ImlValue>,
+    Rc<RefCell<ImlParselet>>,
+    Option<Offset>,
+);
+
 /** Tokay compiler instance
 
 A tokay compiler initializes a Tokay parser for later re-use when called multiple times.
 
 The compiler can be set into an interactive mode so that statics, variables and constants once built
 won't be removed and can be accessed later on. This is useful in REPL mode.
+
+Modules imported by `import "filename"` are searched relative to the importing file first,
+and afterwards in the directories of the search path, which is initialized from the TOKAY_PATH
+environment variable.
 */
 pub struct Compiler {
     parser: Option<parser::Parser>,   // Internal Tokay parser
     pub debug: u8,                    // Compiler debug mode
+    pub search_path: Vec<PathBuf>,    // Directories to search for imported modules
     pub(super) values: Vec<ImlValue>, // Constant values and parselets created during compile
     pub(super) scopes: Vec<Scope>,    // Current compilation scopes
     pub(super) usages: Vec<Result<Vec<ImlOp>, Usage>>, // Usages of symbols in parselets
     pub(super) errors: Vec<Error>,    // Collected errors during compilation
+    pub(super) namespaces: HashMap<String, HashMap<String, ImlValue>>, // Imported namespaces
+    pub(super) module: bool,          // Compiling an imported module
+    pub(super) instances: Vec<Instance>, // Instances of generic parselets defined later
+    pub(super) templates: Vec<Rc<RefCell<ImlTemplate>>>, // Generic parselets being instantiated
+    imports: Vec<PathBuf>,            // Files currently being compiled, for cycle detection
+    modules: HashMap<PathBuf, HashMap<String, ImlValue>>, // Constants of already imported files
+    prelude: HashMap<String, ImlValue>, // Constants defined by the prelude
 }
 
 impl Compiler {
@@ -62,6 +87,16 @@ impl Compiler {
             scopes: Vec::new(),
             usages: Vec::new(),
             errors: Vec::new(),
+            search_path: std::env::var_os("TOKAY_PATH")
+                .map(|path| std::env::split_paths(&path).collect())
+                .unwrap_or_default(),
+            namespaces: HashMap::new(),
+            module: false,
+            instances: Vec::new(),
+            templates: Vec::new(),
+            imports: Vec::new(),
+            modules: HashMap::new(),
+            prelude: HashMap::new(),
         };
 
         // Compile with the default prelude
@@ -69,6 +104,10 @@ impl Compiler {
             compiler
                 .compile_from_str(include_str!("../prelude.tok"))
                 .unwrap(); // this should panic in case of an error!
+
+            if let Some(Scope::Parselet { constants, .. }) = compiler.scopes.last() {
+                compiler.prelude = constants.clone();
+            }
         }
 
         // Set compiler debug level afterwards
@@ -88,6 +127,12 @@ impl Compiler {
             self.parser = Some(Parser::new());
         }
 
+        // Remember the compiled file, so that imports are resolved relative to it
+        let file = reader
+            .source()
+            .and_then(|source| Path::new(source).canonicalize().ok())
+            .filter(|path| path.is_file());
+
         let parser = self.parser.as_ref().unwrap();
         let ast = match parser.parse(reader) {
             Ok(ast) => ast,
@@ -101,8 +146,16 @@ impl Compiler {
             ast::print(&ast);
         }
 
+        if let Some(file) = &file {
+            self.imports.push(file.clone());
+        }
+
         ast::traverse(self, &ast);
 
+        if file.is_some() {
+            self.imports.pop();
+        }
+
         if self.errors.len() > 0 {
             for error in &self.errors {
                 eprintln!("{}", error);
@@ -121,6 +174,268 @@ impl Compiler {
         )))))
     }
 
+    /** Finds the file of an imported module.
+
+    Relative filenames are searched relative to the directory of the importing file, or the
+    working directory, and afterwards in the search path. The extension ".tok" may be omitted. */
+    fn find_module(&self, filename: &str) -> Option<PathBuf> {
+        let filename = Path::new(filename);
+        let mut dirs = Vec::new();
+
+        if !filename.is_absolute() {
+            dirs.push(
+                self.imports
+                    .last()
+                    .and_then(|file| file.parent())
+                    .map(|dir| dir.to_path_buf())
+                    .unwrap_or_default(),
+            );
+            dirs.extend(self.search_path.iter().cloned());
+        } else {
+            dirs.push(PathBuf::new());
+        }
+
+        for dir in dirs {
+            let path = dir.join(filename);
+
+            if path.is_file() {
+                return Some(path);
+            }
+
+            if path.extension().is_none() {
+                let path = path.with_extension("tok");
+
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+        }
+
+        None
+    }
+
+    /** Imports a module from a Tokay source file, and returns the constants it defines.
+
+    The module is compiled in its own scope, where only the prelude and its own imports are
+    visible. Code outside of constants is not executed, so global variables are rejected. Every
+    file is only compiled once, further imports return the already known constants. */
+    pub(super) fn import(
+        &mut self,
+        filename: &str,
+        offset: Option<Offset>,
+    ) -> Option<HashMap<String, ImlValue>> {
+        let (path, file) = match self
+            .find_module(filename)
+            .and_then(|path| Some((path.canonicalize().ok()?, path)))
+        {
+            Some((path, file)) => (path, file),
+            None => {
+                self.errors.push(Error::new(
+                    offset,
+                    format!("Module '{}' not found", filename),
+                ));
+                return None;
+            }
+        };
+
+        if let Some(constants) = self.modules.get(&path) {
+            return Some(constants.clone());
+        }
+
+        // Detect cyclic imports
+        if let Some(start) = self.imports.iter().position(|import| *import == path) {
+            let cycle: Vec<String> = self.imports[start..]
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|import| import.display().to_string())
+                .collect();
+
+            self.errors.push(Error::new(
+                offset,
+                format!("Import cycle detected: {}", cycle.join(" -> ")),
+            ));
+            return None;
+        }
+
+        let mut reader = match File::open(&file) {
+            Ok(file) => Reader::new(Box::new(BufReader::new(file))),
+            Err(err) => {
+                self.errors.push(Error::new(
+                    offset,
+                    format!("Can't read module '{}': {}", filename, err),
+                ));
+                return None;
+            }
+        };
+
+        reader.set_source(&file.to_string_lossy());
+
+        let ast = match self.parser.get_or_insert_with(Parser::new).parse(reader) {
+            Ok(ast) => ast,
+            Err(error) => {
+                self.errors.push(error);
+                return None;
+            }
+        };
+
+        // Compile the module with its own scopes and namespaces
+        let scopes = std::mem::take(&mut self.scopes);
+        let namespaces = std::mem::take(&mut self.namespaces);
+        let instances = std::mem::take(&mut self.instances);
+        let outer_module = std::mem::replace(&mut self.module, true);
+        let usage_start = self.usages.len();
+
+        self.push_parselet();
+
+        if let Scope::Parselet { constants, .. } = &mut self.scopes[0] {
+            *constants = self.prelude.clone();
+        }
+
+        self.imports.push(path.clone());
+        ast::traverse(self, &ast);
+        self.imports.pop();
+
+        // Symbols still unresolved must not be resolved by the importer
+        self.dispose_usages(usage_start);
+        self.dispose_instances();
+        self.instances = instances;
+
+        let module = ImlModule {
+            constants: match self.scopes.pop() {
+                Some(Scope::Parselet { constants, .. }) => constants,
+                _ => unreachable!(),
+            },
+            namespaces: std::mem::replace(&mut self.namespaces, namespaces),
+        };
+
+        self.scopes = scopes;
+        self.module = outer_module;
+
+        // Generic parselets are instantiated within their module
+        let constants: HashMap<String, ImlValue> = module
+            .constants
+            .iter()
+            .filter(|(name, value)| self.prelude.get(*name) != Some(value))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+
+        for value in constants.values() {
+            if let ImlValue::Template(template) = value {
+                template.borrow_mut().module.get_or_insert(module.clone());
+            }
+        }
+
+        self.modules.insert(path, constants.clone());
+        Some(constants)
+    }
+
+    /// Reports usages from `start` on which are still unresolved as errors.
+    pub(super) fn dispose_usages(&mut self, start: usize) {
+        for usage in self.usages[start..].iter_mut() {
+            if usage.is_err() {
+                let error = match std::mem::replace(usage, Ok(vec![ImlOp::Nop])) {
+                    Err(Usage::Load { name, offset } | Usage::CallOrCopy { name, offset }) => {
+                        Error::new(offset, format!("Use of unresolved symbol '{}'", name))
+                    }
+                    Err(Usage::Call { name, offset, .. }) => {
+                        Error::new(offset, format!("Call to unresolved symbol '{}'", name))
+                    }
+                    Err(Usage::Error(error)) => error,
+                    Ok(_) => unreachable!(),
+                };
+
+                self.errors.push(error);
+            }
+        }
+    }
+
+    /// Reports instances of generic parselets which have never been defined as errors.
+    pub(super) fn dispose_instances(&mut self) {
+        for (name, .., offset) in self.instances.drain(..) {
+            self.errors.push(Error::new(
+                offset,
+                format!("Use of undefined generic parselet '{}'", name),
+            ));
+        }
+    }
+
+    /** Disables memoization of parselets which are never re-entered at the same offset.
+
+    This is the case for a parselet which isn't left-recursive, and is only called at the start of
+    one other parselet, which is memoized and not left-recursive. The caller's memo entry already
+    covers any repeated call at the same offset.
+
+    Parselets with a memoization annotation are left as they are. */
+    fn unmemoize(statics: &[RefValue], annotated: &[usize]) {
+        let parselets: Vec<Option<ParseletRef>> = statics
+            .iter()
+            .map(|value| value.borrow().object::<ParseletRef>().cloned())
+            .collect();
+
+        // The main parselet is the last one, and is never memoized
+        let main = parselets.iter().rposition(|parselet| parselet.is_some());
+
+        // Collect the referring parselets of every static
+        let mut callers: Vec<Vec<(usize, bool)>> = vec![Vec::new(); statics.len()];
+
+        for (i, parselet) in parselets.iter().enumerate() {
+            if let Some(parselet) = parselet {
+                for (addr, start) in parselet.0.borrow().references(statics) {
+                    callers[addr].push((i, start));
+                }
+            }
+        }
+
+        // Returns the only caller of a parselet which may lose memoization
+        let candidate = |i: usize| -> Option<usize> {
+            let parselet = parselets[i].as_ref()?.0.borrow();
+
+            if Some(i) == main || annotated.contains(&i) || parselet.consuming != Some(false) {
+                return None;
+            }
+
+            match callers[i][..] {
+                [(caller, true)] if caller != i && Some(caller) != main => {
+                    let caller = parselets[caller].as_ref().unwrap().0.borrow();
+
+                    if caller.consuming == Some(false) && caller.memo {
+                        Some(callers[i][0].0)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            }
+        };
+
+        /*
+            A candidate is only unmemoized when its caller stays memoized, so the decision
+            alternates along a chain of candidates, starting from the first non-candidate.
+        */
+        let mut unmemoized = Vec::new();
+
+        'statics: for i in 0..statics.len() {
+            let mut chain = vec![i];
+
+            while let Some(caller) = candidate(*chain.last().unwrap()) {
+                // Keep memoization on cyclic chains
+                if chain.contains(&caller) {
+                    continue 'statics;
+                }
+
+                chain.push(caller);
+            }
+
+            if chain.len() % 2 == 0 {
+                unmemoized.push(i);
+            }
+        }
+
+        for i in unmemoized {
+            parselets[i].as_ref().unwrap().0.borrow_mut().memo = false;
+        }
+    }
+
     /** Converts the current compiler state into a Program. */
     pub fn finalize(&mut self) -> Result<Program, Vec<Error>> {
         // Check for correct scope level
@@ -128,6 +443,10 @@ impl Compiler {
 
         let mut errors: Vec<Error> = Vec::new();
 
+        // Check and report any generic parselets which have never been defined
+        self.dispose_instances();
+        errors.append(&mut self.errors);
+
         // Check and report any unresolved usages
         let mut usages = self
             .usages
@@ -240,17 +559,29 @@ impl Compiler {
         }
 
         // Compile values into a program
-        let program = Program::new(
-            values
-                .into_iter()
-                .map(|value| match value {
-                    ImlValue::Parselet(parselet) => {
-                        RefValue::from(parselet.borrow().into_parselet())
+        let mut annotated = Vec::new(); // parselets with a memoization annotation
+
+        let statics: Vec<RefValue> = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| match value {
+                ImlValue::Parselet(parselet) => {
+                    let parselet = parselet.borrow();
+
+                    if parselet.memo.is_some() {
+                        annotated.push(i);
                     }
-                    ImlValue::Value(value) => value,
-                })
-                .collect(),
-        );
+
+                    RefValue::from(parselet.into_parselet())
+                }
+                ImlValue::Template(_) => unreachable!("Generic parselets are never static"),
+                ImlValue::Value(value) => value,
+            })
+            .collect();
+
+        Self::unmemoize(&statics, &annotated);
+
+        let program = Program::new(statics);
 
         if self.debug > 0 {
             program.dump();
@@ -429,6 +760,14 @@ impl Compiler {
         None
     }
 
+    /** Checks whether the current parselet scope is the global scope. */
+    pub(super) fn is_global_scope(&self) -> bool {
+        self.scopes
+            .iter()
+            .position(|scope| matches!(scope, Scope::Parselet { .. }))
+            == Some(self.scopes.len() - 1)
+    }
+
     /** Insert new local variable under given name in current scope. */
     pub(super) fn new_local(&mut self, name: &str) -> usize {
         for scope in &mut self.scopes {
@@ -447,6 +786,19 @@ impl Compiler {
         unreachable!("There _must_ be at least one parselet scope!");
     }
 
+    /** Create a new local variable which can't be accessed by name, e.g. to hold an iterator. */
+    pub(super) fn new_temporary(&mut self) -> String {
+        for scope in &mut self.scopes {
+            if let Scope::Parselet { variables, .. } = scope {
+                let name = format!("#{}", variables.len());
+                variables.insert(name.clone(), variables.len());
+                return name;
+            }
+        }
+
+        unreachable!("There _must_ be at least one parselet scope!");
+    }
+
     /** Retrieve address of a global variable. */
     pub(super) fn get_global(&self, name: &str) -> Option<usize> {
         if let Scope::Parselet { variables, .. } = self.scopes.last().unwrap() {
@@ -538,6 +890,15 @@ impl Compiler {
     /** Get constant value, either from current or preceding scope,
     a builtin or special. */
     pub(super) fn get_constant(&mut self, name: &str) -> Option<ImlValue> {
+        // Constants qualified by a namespace are taken from the imported module
+        if let Some((namespace, name)) = name.split_once("::") {
+            return self
+                .namespaces
+                .get(namespace)
+                .and_then(|constants| constants.get(name))
+                .cloned();
+        }
+
         // Check for constant in available scopes
         for scope in &self.scopes {
             if let Scope::Parselet { constants, .. } | Scope::Block { constants, .. } = scope {
@@ -749,3 +1110,19 @@ fn test_prelude() {
         ])))
     );
 }
+
+#[test]
+// Tests that imported modules are compiled only once
+fn test_import_once() {
+    let mut compiler = Compiler::new(true);
+
+    compiler
+        .compile_from_str("import \"tests/lib/chars.tok\"\nimport \"tests/lib/chars\" as c")
+        .unwrap();
+
+    assert_eq!(compiler.modules.len(), 1);
+    assert_eq!(
+        compiler.get_constant("chars::Alpha"),
+        compiler.get_constant("c::Alpha")
+    );
+}
@@ -71,8 +71,9 @@ impl Compileable for ImlLoop {
 
         body.extend(self.body.compile(parselet));
 
+        // The loop frame's size addresses its last operation, where a break continues behind
         ret.push(Op::Loop(
-            body.len() + if self.consuming.is_some() { 3 } else { 2 },
+            body.len() + if self.consuming.is_some() { 3 } else { 1 },
         ));
         if self.consuming.is_some() {
             ret.push(Op::Fuse(body.len() + 2));
@@ -9,9 +9,12 @@ mod not;
 mod op;
 mod parselet;
 mod peek;
+mod recover;
 mod repeat;
 mod result;
 mod sequence;
+mod template;
+mod try_;
 mod value;
 
 pub use alt::*;
@@ -22,9 +25,12 @@ pub use not::*;
 pub use op::*;
 pub use parselet::*;
 pub use peek::*;
+pub use recover::*;
 pub use repeat::*;
 pub(super) use result::*;
 pub use sequence::*;
+pub use template::*;
+pub use try_::*;
 pub(super) use value::*;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -9,10 +9,13 @@ pub struct ImlParselet {
     pub severity: u8,                            // Capture push severity
     pub name: Option<String>,                    // Parselet's name from source (for debugging)
     pub signature: Vec<(String, Option<usize>)>, // Argument signature with default arguments
-    locals: usize,                               // Number of local variables present
-    begin: ImlOp,                                // Begin-operations
-    end: ImlOp,                                  // End-operations
-    body: ImlOp,                                 // Operations
+    pub memo: Option<bool>,                      // Memoization annotation, None for default
+    pub args_catchall: Option<usize>,            // Local receiving further arguments (*args)
+    pub nargs_catchall: Option<usize>, // Local receiving further named arguments (**nargs)
+    locals: usize,                     // Number of local variables present
+    begin: ImlOp,                      // Begin-operations
+    end: ImlOp,                        // End-operations
+    body: ImlOp,                       // Operations
 }
 
 impl ImlParselet {
@@ -35,6 +38,9 @@ impl ImlParselet {
             consuming: None,
             severity: 5,
             signature,
+            memo: None,
+            args_catchall: None,
+            nargs_catchall: None,
             locals,
             begin,
             end,
@@ -44,7 +50,7 @@ impl ImlParselet {
 
     // Turns an ImlParselet in to a parselet
     pub fn into_parselet(&self /* fixme: change to self without & later on... */) -> Parselet {
-        Parselet::new(
+        let mut parselet = Parselet::new(
             self.name.clone(),
             if let Some(Consumable { leftrec, .. }) = self.consuming {
                 Some(leftrec)
@@ -57,7 +63,12 @@ impl ImlParselet {
             self.begin.compile(&self),
             self.end.compile(&self),
             self.body.compile(&self),
-        )
+        );
+
+        parselet.memo = self.memo.unwrap_or(true);
+        parselet.args_catchall = self.args_catchall;
+        parselet.nargs_catchall = self.nargs_catchall;
+        parselet
     }
 
     pub fn resolve(&mut self, usages: &mut Vec<Vec<ImlOp>>) {
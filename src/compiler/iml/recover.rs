@@ -0,0 +1,55 @@
+use super::*;
+
+/** Recovery construct.
+
+This construct runs its body, and catches any error raised inside. The error is recorded by the
+runtime, input is skipped behind the synchronization string, and an error node is returned instead.
+*/
+
+#[derive(Debug)]
+pub struct ImlRecover {
+    body: ImlOp,
+    sync: ImlOp,
+}
+
+impl ImlRecover {
+    pub fn new(body: ImlOp, sync: ImlOp) -> ImlOp {
+        Self { body, sync }.into_op()
+    }
+}
+
+impl Compileable for ImlRecover {
+    fn resolve(&mut self, usages: &mut Vec<Vec<ImlOp>>) {
+        self.body.resolve(usages);
+        self.sync.resolve(usages);
+    }
+
+    fn finalize(
+        &mut self,
+        values: &Vec<ImlValue>,
+        stack: &mut Vec<(usize, bool)>,
+    ) -> Option<Consumable> {
+        self.sync.finalize(values, stack);
+        self.body.finalize(values, stack)
+    }
+
+    fn compile(&self, parselet: &ImlParselet) -> Vec<Op> {
+        let body = self.body.compile(parselet);
+        let sync = self.sync.compile(parselet);
+
+        let mut ret = vec![Op::Try(body.len() + 2)];
+
+        ret.extend(body);
+        ret.push(Op::Forward(sync.len() + 2));
+        ret.extend(sync);
+        ret.extend(vec![Op::Recover, Op::Close]);
+
+        ret
+    }
+}
+
+impl std::fmt::Display for ImlRecover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "recover({}, {})", self.body, self.sync)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::compiler::*;
+use crate::error::Error;
 use crate::reader::Offset;
 use crate::value::{Object, RefValue, Value};
 use num::ToPrimitive;
@@ -24,6 +25,17 @@ impl ImlResult {
     pub fn into_ops(self, compiler: &mut Compiler, call: bool) -> Vec<ImlOp> {
         match self {
             ImlResult::Empty => Vec::new(),
+            ImlResult::Value(ImlValue::Template(template)) => {
+                compiler.errors.push(Error::new(
+                    None,
+                    format!(
+                        "Generic parselet '{}' must be defined as constant",
+                        template.borrow().name.as_deref().unwrap_or("<unnamed>")
+                    ),
+                ));
+
+                Vec::new()
+            }
             ImlResult::Value(value) => {
                 vec![ImlOp::Op(if call && value.is_callable(true) {
                     if value.is_consuming() {
@@ -0,0 +1,61 @@
+//! Intermediate representation of a generic parselet
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::*;
+use crate::value::RefValue;
+
+/** Generic parselet, which is instantiated at compile-time for every set of generic arguments.
+
+The template holds the AST of the parselet, which is traversed again for every instance with
+the generic parameters defined as constants. */
+#[derive(Debug)]
+pub struct ImlTemplate {
+    pub name: Option<String>,                      // Template's name from source
+    pub generics: Vec<(String, Option<ImlValue>)>, // Generic parameters with default values
+    pub node: RefValue,                            // AST of the parselet without its generics
+    pub module: Option<ImlModule>,                 // Module the template was imported from
+    pub(in crate::compiler) instances: Vec<(Vec<ImlValue>, ImlValue)>, // Already known instances
+}
+
+/// Constants and namespaces of an imported module, to instantiate its templates with.
+#[derive(Debug, Clone)]
+pub struct ImlModule {
+    pub constants: HashMap<String, ImlValue>,
+    pub namespaces: HashMap<String, HashMap<String, ImlValue>>,
+}
+
+impl ImlTemplate {
+    pub fn new(generics: Vec<(String, Option<ImlValue>)>, node: RefValue) -> Self {
+        Self {
+            name: None,
+            generics,
+            node,
+            module: None,
+            instances: Vec::new(),
+        }
+    }
+
+    /// Returns the instance for the given generic arguments, when already known.
+    pub fn instance(&self, args: &[ImlValue]) -> Option<ImlValue> {
+        self.instances
+            .iter()
+            .find(|(known, _)| known == args)
+            .map(|(_, instance)| instance.clone())
+    }
+}
+
+impl From<ImlTemplate> for ImlValue {
+    fn from(template: ImlTemplate) -> Self {
+        Self::Template(Rc::new(RefCell::new(template)))
+    }
+}
+
+impl std::cmp::PartialEq for ImlTemplate {
+    // It satisfies to just compare the template's memory address for equality
+    fn eq(&self, other: &Self) -> bool {
+        self as *const ImlTemplate as usize == other as *const ImlTemplate as usize
+    }
+}
@@ -0,0 +1,72 @@
+use super::*;
+
+/** Try-catch construct.
+
+This construct runs its body, and catches any error raised inside. Reading is reset to where the
+body started, and the catch-part is run with the error as value, which is stored by the catch-ops.
+*/
+
+#[derive(Debug)]
+pub struct ImlTry {
+    body: ImlOp,
+    catch: ImlOp,
+}
+
+impl ImlTry {
+    pub fn new(body: ImlOp, catch: ImlOp) -> ImlOp {
+        Self { body, catch }.into_op()
+    }
+}
+
+impl Compileable for ImlTry {
+    fn resolve(&mut self, usages: &mut Vec<Vec<ImlOp>>) {
+        self.body.resolve(usages);
+        self.catch.resolve(usages);
+    }
+
+    fn finalize(
+        &mut self,
+        values: &Vec<ImlValue>,
+        stack: &mut Vec<(usize, bool)>,
+    ) -> Option<Consumable> {
+        let body = self.body.finalize(values, stack);
+        let catch = self.catch.finalize(values, stack);
+
+        match (body, catch) {
+            (Some(body), Some(catch)) => Some(Consumable {
+                leftrec: body.leftrec || catch.leftrec,
+                nullable: body.nullable || catch.nullable,
+            }),
+            // A part which doesn't consume makes the entire construct nullable
+            (Some(consumable), None) | (None, Some(consumable)) => Some(Consumable {
+                leftrec: consumable.leftrec,
+                nullable: true,
+            }),
+            (None, None) => None,
+        }
+    }
+
+    fn compile(&self, parselet: &ImlParselet) -> Vec<Op> {
+        let body = self.body.compile(parselet);
+        let catch = self.catch.compile(parselet);
+
+        // Both parts collect their result inside of the try-frame
+        let mut ret = vec![Op::Try(body.len() + 3)];
+
+        ret.extend(body);
+        ret.push(Op::Collect(0, 5));
+        ret.push(Op::Forward(catch.len() + 3));
+        ret.push(Op::Catch);
+        ret.extend(catch);
+        ret.push(Op::Collect(0, 5));
+        ret.push(Op::Close);
+
+        ret
+    }
+}
+
+impl std::fmt::Display for ImlTry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "try {} catch {}", self.body, self.catch)
+    }
+}
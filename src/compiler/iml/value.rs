@@ -9,6 +9,7 @@ use crate::value::{Object, RefValue};
 #[derive(Clone, Debug, PartialEq)]
 pub enum ImlValue {
     Parselet(Rc<RefCell<ImlParselet>>),
+    Template(Rc<RefCell<ImlTemplate>>),
     Value(RefValue),
 }
 
@@ -35,6 +36,7 @@ impl ImlValue {
                     true
                 }
             }
+            ImlValue::Template(_) => false,
             ImlValue::Value(value) => value.is_callable(without_arguments),
         }
     }
@@ -43,6 +45,7 @@ impl ImlValue {
     pub fn is_consuming(&self) -> bool {
         match self {
             ImlValue::Parselet(parselet) => parselet.borrow().consuming.is_some(),
+            ImlValue::Template(_) => false,
             ImlValue::Value(value) => value.is_consuming(),
         }
     }
@@ -57,6 +60,7 @@ impl ImlValue {
                     false
                 }
             }
+            ImlValue::Template(_) => false,
             ImlValue::Value(value) => value.is_nullable(),
         }
     }
@@ -73,6 +77,14 @@ impl std::fmt::Display for ImlValue {
                     .as_ref()
                     .unwrap_or(&"<unnamed parselet>".to_string())
             ),
+            Self::Template(t) => write!(
+                f,
+                "{}",
+                t.borrow()
+                    .name
+                    .as_ref()
+                    .unwrap_or(&"<unnamed generic parselet>".to_string())
+            ),
             Self::Value(v) => write!(f, "{}", v.repr()),
         }
     }
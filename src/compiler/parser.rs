@@ -37,6 +37,10 @@ impl Parser {
             [(peek (not (token (Token::Char(charclass!['A' => 'Z', 'a' => 'z'] + charclass!['_']))))), _]
         }),
 
+        (_SeparatedFlag = {  // helper parselet to ensure that a flag is not followed by an identifier
+            [(peek (not (token (Token::Char(charclass!['A' => 'Z', 'a' => 'z', '0' => '9'] + charclass!['_'])))))]
+        }),
+
         (T_EOL = {  // end-of-line
             ["\n", _],  // unix/linux
             ["\r", (opt "\n"), _],  // classic mac & windows
@@ -67,6 +71,8 @@ impl Parser {
                 (Op::Rust(Rust(|context| code_to_char(context, 0, 8))))],
             ["x", T_HexDigit, T_HexDigit,
                 (Op::Rust(Rust(|context| code_to_char(context, 1, 16))))],
+            ["u", "{", (token (Token::Chars(charclass!['0' => '9', 'A' => 'F', 'a' => 'f']))), "}",
+                (Op::Rust(Rust(|context| code_to_char(context, 2, 16))))],
             ["u", T_HexDigit, T_HexDigit, T_HexDigit, T_HexDigit,
                 (Op::Rust(Rust(|context| code_to_char(context, 1, 16))))],
             ["U", T_HexDigit, T_HexDigit, T_HexDigit, T_HexDigit,
@@ -77,11 +83,18 @@ impl Parser {
 
         // Prime Tokens
 
-        (T_Identifier = {  // any identifier
+        (T_Identifier = {  // any identifier, optionally qualified by a namespace
+            [(call Ident[]), "::", (call Ident[]), (call ast[(value "identifier"), (Op::LoadFastCapture(0))])],
             (call ast[(value "identifier"), (call Ident[])])
         }),
 
-        (T_Consumable = {  // consumable identifier
+        (T_Consumable = {  // consumable identifier, optionally qualified by a namespace
+            [
+                (call Ident[]), "::",
+                (token (Token::Char(charclass!['A' => 'Z'] + charclass!['_']))),
+                (opt (token (Token::Chars(charclass!['A' => 'Z', 'a' => 'z', '0' => '9'] + charclass!['_'])))),
+                (call ast[(value "identifier"), (Op::LoadFastCapture(0))])
+            ],
             [
                 (token (Token::Char(charclass!['A' => 'Z'] + charclass!['_']))),
                 (opt (token (Token::Chars(charclass!['A' => 'Z', 'a' => 'z', '0' => '9'] + charclass!['_'])))),
@@ -89,6 +102,14 @@ impl Parser {
             ]
         }),
 
+        (T_Variable = {  // plain variable identifier, not qualified by a namespace
+            [
+                (token (Token::Char(charclass!['a' => 'z']))),
+                (opt (token (Token::Chars(charclass!['A' => 'Z', 'a' => 'z', '0' => '9'] + charclass!['_'])))),
+                (call ast[(value "identifier"), (Op::LoadFastCapture(0))])
+            ]
+        }),
+
         (T_Alias = {  // T_Alias is an identifier treated as string value
             [
                 (token (Token::Char(charclass!['A' => 'Z', 'a' => 'z'] + charclass!['_']))),
@@ -123,6 +144,21 @@ impl Parser {
             ]
         }),
 
+        (T_Regex = {
+            [
+                "\'",  // a regular expression, where only \' is escaped
+                (kle {
+                    ["\\", "\'", (value "\'")],
+                    ["\\", "\\", (value "\\\\")],
+                    [(token (Token::Chars(charclass!['\\', '\''].negate())))],
+                    [(token (Token::Char(charclass!['\\'])))],
+                    [EOF, (call error[(value "Unclosed regular expression, expecting '\''")])]
+                }),
+                (call str_join[(value ""), (Op::LoadFastCapture(2))]),
+                (expect "\'")
+            ]
+        }),
+
         (T_Integer = {
             [(call ast[(value "value_integer"), (call Int[])])]
         }),
@@ -139,8 +175,19 @@ impl Parser {
             [EOF, (call error[(value "Unclosed character-class, expecting ']'")])]
         }),
 
+        (CclProperty = {  // name of a Unicode property, like Greek, Lu or Script=Latin
+            [
+                (token (Token::Chars(charclass!['A' => 'Z', 'a' => 'z', '0' => '9'] + charclass!['_', '=']))),
+                (expect '}')
+            ]
+        }),
+
         (CclRange = {
-            [CclChar, "-", CclChar,
+            ['\\', 'p', '{', CclProperty, (call ast[(value "property")])],
+            ['\\', 'P', '{', CclProperty, (call ast[(value "property_neg")])],
+            ["&&", (peek (not ']')), (call ast[(value "intersect")])],
+            ["--", (peek (not ']')), (call ast[(value "difference")])],
+            [CclChar, "-", (peek (not '-')), CclChar,
                 (call ast[
                     (value "range"),
                     [
@@ -187,6 +234,10 @@ impl Parser {
             }), (call ast[(value "lvalue")])]
         }),
 
+        (CatchVariable = {  // plain variable receiving the error caught by try
+            [T_Variable, (not {"(", "::", ".", "["}), (call ast[(value "lvalue")])]
+        }),
+
         (Load = {
             [Lvalue, "++", (call ast[(value "inplace_post_inc")])],
             [Lvalue, "--", (call ast[(value "inplace_post_dec")])],
@@ -232,17 +283,38 @@ impl Parser {
             (pos [CallParameter, (opt [",", _]), ___])
         }),
 
+        // Generics
+
+        (GenericValue = {
+            Literal,
+            Token
+        }),
+
+        (Generic = {  // instance of a generic parselet
+            [T_Consumable, "<", _, (pos [GenericValue, _, (opt [",", _])]), ">",
+                (call ast[(value "generic")])]
+        }),
+
         // Tokens
 
         (TokenLiteral = {
+            ["'", T_Touch, "'", "i", _SeparatedFlag, (call ast[(value "value_token_match_icase")])],
             ["'", T_Touch, "'", (call ast[(value "value_token_match")])],
+            [T_Touch, "i", _SeparatedFlag, (call ast[(value "value_token_touch_icase")])],
             [T_Touch, (call ast[(value "value_token_touch")])],
             [".", (call ast[(value "value_token_any")])],
-            ['[', Ccl, ']', (call ast[(value "value_token_ccl")])]
+            ['[', Ccl, ']', "i", _SeparatedFlag, (call ast[(value "value_token_ccl_icase")])],
+            ['[', Ccl, ']', "b", _SeparatedFlag, (call ast[(value "value_token_byteclass")])],
+            ['[', Ccl, ']', (call ast[(value "value_token_ccl")])],
+            ["b", T_Touch, (call ast[(value "value_token_bytes")])],
+            ["r", T_Regex, (call ast[(value "value_token_regex")])]
         }),
 
         (TokenCall = {
             TokenLiteral,
+            [Generic, "(", _, ___, (opt CallParameters), ___, (expect ")"),
+                (call ast[(value "call")])],
+            [Generic, (call ast[(value "call")])],
             [T_Consumable, "(", _, ___, (opt CallParameters), ___, (expect ")"),
                 (call ast[(value "call")])],
             [T_Consumable, (call ast[(value "call")])],
@@ -260,7 +332,13 @@ impl Parser {
             TokenCall,
             ["peek", _SeparatedIdentifier, (expect Token), (call ast[(value "op_mod_peek")])],
             ["not", _SeparatedIdentifier, (expect Token), (call ast[(value "op_mod_not")])],
-            ["expect", _SeparatedIdentifier, (expect Token), (call ast[(value "op_mod_expect")])]
+            ["expect", _SeparatedIdentifier, (expect Token), (call ast[(value "op_mod_expect")])],
+            ["recover", _, "(", _, ___, (expect Token), ___,
+                (opt {
+                    [",", _, ___, "sync", _, "=", _, (expect Expression), ___],
+                    [",", _, ___, (expect Expression), ___]
+                }),
+                (expect ")"), (call ast[(value "op_recover")])]
         }),
 
         // Literals
@@ -289,11 +367,20 @@ impl Parser {
                     (call ast[(value "op_if")])],
 
             // for
-            //["for", _SeparatedIdentifier, T_Identifier, _, "in", _SeparatedIdentifier, Expression, Statement,
-            //    (call ast[(value "op_for_in")])],
+            ["for", _SeparatedIdentifier, Lvalue, _, (opt [",", _, Lvalue, _]), "in", _SeparatedIdentifier,
+                Expression, _, Statement, (call ast[(value "op_for_in")])],
             ["for", _SeparatedIdentifier, StatementOrEmpty, ";", _, StatementOrEmpty, ";", _, StatementOrEmpty,
                 StatementOrEmpty, (call ast[(value "op_for")])],
-            ["for", _SeparatedIdentifier, (call error[(value "'for': Expecting start; condition; iter; statement")])],
+            ["for", _SeparatedIdentifier, (call error[(value "'for': Expecting variable in iterable; or start; condition; iter; statement")])],
+
+            // try
+            ["try", _SeparatedIdentifier, Statement, ___, "catch", _SeparatedIdentifier, CatchVariable, _, Statement,
+                (call ast[(value "op_try")])],
+            ["try", _SeparatedIdentifier, Statement, ___, "catch", _SeparatedIdentifier, Block,
+                (call ast[(value "op_try")])],
+            ["try", _SeparatedIdentifier, Statement, ___, "catch", _SeparatedIdentifier,
+                (call error[(value "'catch': Expecting variable and statement, or a { ... } block without variable")])],
+            ["try", _SeparatedIdentifier, (call error[(value "'try': Expecting statement; catch variable statement")])],
 
             // loop
             ["loop", _SeparatedIdentifier, Expression, _, Statement, (call ast[(value "op_loop")])],
@@ -378,6 +465,7 @@ impl Parser {
             ["accept", _SeparatedIdentifier, (opt Expression), (call ast[(value "op_accept")])],
             ["break", _SeparatedIdentifier, (opt Expression), (call ast[(value "op_break")])],
             ["continue", _SeparatedIdentifier, (opt Expression), (call ast[(value "op_continue")])],
+            ["cut", _SeparatedIdentifier, (call ast[(value "op_cut")])],
             ["exit", _SeparatedIdentifier, (opt Expression), (call ast[(value "op_exit")])],
             ["next", _SeparatedIdentifier, (call ast[(value "op_next")])],
             ["push", _SeparatedIdentifier, (opt Expression), (call ast[(value "op_push")])],
@@ -399,11 +487,26 @@ impl Parser {
         // Parselet
 
         (Parselet = {
-            ["@", _, (opt Arguments), Block, (call ast[(value "value_parselet")])],
-            ["@", _, (opt Arguments), Token, (call ast[(value "value_parselet")])]
+            ["@", _, (opt Generics), Annotations, (opt Arguments), Block, (call ast[(value "value_parselet")])],
+            ["@", _, (opt Generics), (opt Arguments), Block, (call ast[(value "value_parselet")])],
+            ["@", _, (opt Generics), (opt Arguments), Token, (call ast[(value "value_parselet")])]
+        }),
+
+        (Generics = {
+            ["<", _, (pos [GenericParameter, (opt [",", _])]), (expect ">"), _, (call ast[(value "generics")])]
+        }),
+
+        (GenericParameter = {
+            [T_Identifier, _, (opt ["=", _, (expect GenericValue), _]), (call ast[(value "gen")])]
+        }),
+
+        (Annotations = {
+            ['[', _, (pos [T_Identifier, _, (opt [',', _])]), ']', _, (call ast[(value "annotations")])]
         }),
 
         (Argument = {
+            ["**", _, T_Identifier, _, (call ast[(value "arg_nargs")])],
+            ["*", _, T_Identifier, _, (call ast[(value "arg_args")])],
             [T_Identifier, _, (opt ["=", _, (opt Expression)]), (call ast[(value "arg")])]
         }),
 
@@ -436,6 +539,10 @@ impl Parser {
         (Instruction = {
             ["begin", _SeparatedIdentifier, Sequence, (expect T_EOL), (call ast[(value "begin")])],
             ["end", _SeparatedIdentifier, Sequence, (expect T_EOL), (call ast[(value "end")])],
+            ["import", _SeparatedIdentifier, T_String, _, "as", _SeparatedIdentifier, (expect T_Identifier), _,
+                (expect T_EOL), (call ast[(value "import")])],
+            ["import", _SeparatedIdentifier, T_String, _, (expect T_EOL), (call ast[(value "import")])],
+            ["import", _SeparatedIdentifier, (call error[(value "'import': Expecting \"filename\" or \"filename\" as name")])],
             [T_Identifier, _, ":", _, {
                 [Statement, (peek T_EOL)],
                 Sequences
@@ -525,13 +632,9 @@ fn code_to_char(context: &mut Context, skip: u8, base: u32) -> Result<Accept, Re
     let value = context.get_capture(0).unwrap();
     let value = value.borrow();
     let slice = &value.object::<Str>().unwrap().as_str()[skip as usize..];
+    let slice = slice.trim_end_matches('}');
 
-    let code = if slice.len() <= 2 {
-        u8::from_str_radix(slice, base).unwrap_or_default() as char
-    } else {
-        std::char::from_u32(u32::from_str_radix(slice, base).unwrap_or_default())
-            .unwrap_or_default()
-    };
+    let code = crate::utils::code_to_char(slice, base);
 
     Ok(Accept::Return(Some(RefValue::from(format!("{}", code)))))
 }
@@ -546,6 +649,26 @@ fn parser_eol() {
     }
 }
 
+#[test]
+// Byte classes don't collide with subscripts of a variable named "b"
+fn parser_byteclass() {
+    use crate::value::Object;
+
+    let parse = |src: &'static str| {
+        Parser::new()
+            .parse(Reader::new(Box::new(std::io::BufReader::new(
+                std::io::Cursor::new(src),
+            ))))
+            .unwrap()
+    };
+
+    let ast = parse("b = (1, 2, 3); print(b[1])").borrow().repr();
+    assert!(ast.contains("\"index\"") && !ast.contains("value_token_byteclass"));
+
+    let ast = parse("[\\x80-\\xff]b").borrow().repr();
+    assert!(ast.contains("value_token_byteclass"));
+}
+
 // Tests for parsing and packrat features ---------------------------------------------------------
 
 /*
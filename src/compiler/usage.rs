@@ -33,6 +33,25 @@ impl Usage {
     pub fn try_resolve(&mut self, compiler: &mut Compiler) -> Option<Vec<ImlOp>> {
         let mut ret: Vec<ImlOp> = Vec::new();
 
+        // Generic parselets can only be used with generic arguments
+        if let Usage::Load { name, offset }
+        | Usage::CallOrCopy { name, offset }
+        | Usage::Call { name, offset, .. } = self
+        {
+            if let Some(ImlValue::Template(_)) = compiler.get_constant(name) {
+                let error = Error::new(
+                    offset.clone(),
+                    format!(
+                        "Generic parselet '{}' requires generic arguments, e.g. '{}<...>'",
+                        name, name
+                    ),
+                );
+
+                *self = Usage::Error(error);
+                return None;
+            }
+        }
+
         match self {
             Usage::Load { name, offset: _ } => {
                 if let Some(value) = compiler.get_constant(&name) {
@@ -48,7 +67,7 @@ impl Usage {
                 if let Some(value) = compiler.get_constant(&name) {
                     if value.is_callable(true) {
                         if let Some(offset) = offset {
-                            ret.push(Op::Offset(Box::new(*offset)).into());
+                            ret.push(Op::Offset(Box::new(offset.clone())).into());
                         }
 
                         ret.push(Op::CallStatic(compiler.define_value(value)).into());
@@ -57,14 +76,14 @@ impl Usage {
                     }
                 } else if let Some(addr) = compiler.get_local(&name) {
                     if let Some(offset) = offset {
-                        ret.push(Op::Offset(Box::new(*offset)).into());
+                        ret.push(Op::Offset(Box::new(offset.clone())).into());
                     }
 
                     ret.push(Op::LoadFast(addr).into());
                     ret.push(Op::CallOrCopy.into());
                 } else if let Some(addr) = compiler.get_global(&name) {
                     if let Some(offset) = offset {
-                        ret.push(Op::Offset(Box::new(*offset)).into());
+                        ret.push(Op::Offset(Box::new(offset.clone())).into());
                     }
 
                     ret.push(Op::LoadGlobal(addr).into());
@@ -81,7 +100,7 @@ impl Usage {
                 // Resolve constants
                 if let Some(value) = compiler.get_constant(&name) {
                     if let Some(offset) = offset {
-                        ret.push(Op::Offset(Box::new(*offset)).into());
+                        ret.push(Op::Offset(Box::new(offset.clone())).into());
                     }
 
                     let addr = compiler.define_value(value);
@@ -90,7 +109,7 @@ impl Usage {
                         ret.push(Op::CallStatic(addr).into());
                     } else if *args > 0 && *nargs == 0 {
                         if let Some(offset) = offset {
-                            ret.push(Op::Offset(Box::new(*offset)).into());
+                            ret.push(Op::Offset(Box::new(offset.clone())).into());
                         }
 
                         ret.push(Op::CallStaticArg(Box::new((addr, *args))).into());
@@ -99,7 +118,7 @@ impl Usage {
                     }
                 } else if let Some(addr) = compiler.get_local(&name) {
                     if let Some(offset) = offset {
-                        ret.push(Op::Offset(Box::new(*offset)).into());
+                        ret.push(Op::Offset(Box::new(offset.clone())).into());
                     }
 
                     if *args == 0 && *nargs == 0 {
@@ -114,7 +133,7 @@ impl Usage {
                     }
                 } else if let Some(addr) = compiler.get_global(&name) {
                     if let Some(offset) = offset {
-                        ret.push(Op::Offset(Box::new(*offset)).into());
+                        ret.push(Op::Offset(Box::new(offset.clone())).into());
                     }
 
                     if *args == 0 && *nargs == 0 {
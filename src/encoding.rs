@@ -0,0 +1,400 @@
+//! Transcoding of input in different encodings into UTF-8, to be read by a Reader.
+use std::io::{self, prelude::*};
+
+/// Input encodings supported by the Decoder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+}
+
+impl Encoding {
+    /// Find encoding by a name, like "utf-16le", "latin1" or "cp1252".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Some(Self::Utf8),
+            "utf-16le" | "utf16le" => Some(Self::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Self::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" => Some(Self::Latin1),
+            "windows-1252" | "cp1252" => Some(Self::Windows1252),
+            _ => None,
+        }
+    }
+
+    /// The encoding's canonical name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Latin1 => "ISO-8859-1",
+            Self::Windows1252 => "Windows-1252",
+        }
+    }
+
+    /// Detects an encoding from a byte order mark, returning the encoding and the length of the mark.
+    fn detect(bytes: &[u8]) -> Option<(Self, usize)> {
+        if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+            Some((Self::Utf8, 3))
+        } else if bytes.starts_with(&[0xff, 0xfe]) {
+            Some((Self::Utf16Le, 2))
+        } else if bytes.starts_with(&[0xfe, 0xff]) {
+            Some((Self::Utf16Be, 2))
+        } else {
+            None
+        }
+    }
+}
+
+// Windows-1252 characters in range 0x80-0x9f; unassigned bytes are '\0'.
+const WINDOWS1252: [char; 32] = [
+    '€', '\0', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\0', 'Ž', '\0', '\0', '‘',
+    '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\0', 'ž', 'Ÿ',
+];
+
+/** Buffered reader transcoding input of a given encoding into UTF-8.
+
+When no encoding is specified, the encoding is detected from a byte order mark, with UTF-8 as
+fallback. A byte order mark of the input is always removed.
+
+Input which can't be decoded results in an io::Error of kind InvalidData, after any input
+decoded before has been read. In replacement mode, such input is replaced by U+FFFD instead. */
+pub struct Decoder {
+    reader: Box<dyn BufRead>,   // Reader object to read from
+    encoding: Option<Encoding>, // Encoding, None until detected
+    replace: bool,              // Replacement mode
+    input: Vec<u8>,             // Pending, undecoded input
+    buffer: Vec<u8>,            // Decoded UTF-8 output
+    pos: usize,                 // Read position in buffer
+    error: Option<String>,      // Pending decoding error
+    started: bool,              // Byte order mark has been checked
+    eof: bool,                  // EOF marker
+}
+
+impl Decoder {
+    /// Creates a new decoder; With no encoding given, the encoding is detected.
+    pub fn new(reader: Box<dyn BufRead>, encoding: Option<Encoding>) -> Self {
+        Self {
+            reader,
+            encoding,
+            replace: false,
+            input: Vec::new(),
+            buffer: Vec::new(),
+            pos: 0,
+            error: None,
+            started: false,
+            eof: false,
+        }
+    }
+
+    /// Enables or disables the replacement of undecodable input by U+FFFD.
+    pub fn set_replace(&mut self, replace: bool) {
+        self.replace = replace;
+    }
+
+    /// Returns the encoding used, which is None when not detected yet.
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// Internal function to handle undecodable input; Returns false when decoding must stop.
+    fn invalid(&mut self) -> bool {
+        if self.replace {
+            self.buffer.extend_from_slice("\u{fffd}".as_bytes());
+            true
+        } else {
+            self.error = Some(format!(
+                "Invalid {} input",
+                self.encoding.unwrap_or(Encoding::Utf8).name()
+            ));
+            false
+        }
+    }
+
+    /// Internal function to read and decode the next chunk of input.
+    fn decode(&mut self) -> io::Result<()> {
+        let chunk = self.reader.fill_buf()?;
+        let len = chunk.len();
+
+        if len == 0 {
+            self.eof = true;
+        } else {
+            self.input.extend_from_slice(chunk);
+            self.reader.consume(len);
+        }
+
+        // Check for a byte order mark, which needs at least 3 bytes
+        if !self.started {
+            if self.input.len() < 3 && !self.eof {
+                return Ok(());
+            }
+
+            self.started = true;
+
+            if let Some((encoding, bom)) = Encoding::detect(&self.input) {
+                if self.encoding.is_none() || self.encoding == Some(encoding) {
+                    self.encoding = Some(encoding);
+                    self.input.drain(..bom);
+                }
+            }
+
+            self.encoding.get_or_insert(Encoding::Utf8);
+        }
+
+        let input = std::mem::take(&mut self.input);
+        let mut i = 0;
+
+        match self.encoding.unwrap() {
+            Encoding::Utf8 => {
+                while i < input.len() {
+                    match std::str::from_utf8(&input[i..]) {
+                        Ok(s) => {
+                            self.buffer.extend_from_slice(s.as_bytes());
+                            i = input.len();
+                        }
+                        Err(err) => {
+                            let valid = i + err.valid_up_to();
+                            self.buffer.extend_from_slice(&input[i..valid]);
+                            i = valid;
+
+                            match err.error_len() {
+                                // Incomplete sequence at the end of input
+                                None if !self.eof => break,
+                                None => i = input.len(),
+                                Some(len) => i += len,
+                            }
+
+                            if !self.invalid() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+
+            encoding @ (Encoding::Utf16Le | Encoding::Utf16Be) => {
+                let unit = |i: usize| {
+                    let bytes = [input[i], input[i + 1]];
+
+                    if encoding == Encoding::Utf16Le {
+                        u16::from_le_bytes(bytes)
+                    } else {
+                        u16::from_be_bytes(bytes)
+                    }
+                };
+
+                while i < input.len() {
+                    // Incomplete code unit or surrogate pair
+                    if i + 1 >= input.len()
+                        || ((0xd800..0xdc00).contains(&unit(i)) && i + 3 >= input.len())
+                    {
+                        if !self.eof {
+                            break;
+                        }
+
+                        i = input.len();
+
+                        if !self.invalid() {
+                            break;
+                        }
+
+                        continue;
+                    }
+
+                    let first = unit(i);
+
+                    let (ch, len) = match first {
+                        0xd800..=0xdbff => match unit(i + 2) {
+                            second @ 0xdc00..=0xdfff => (
+                                char::from_u32(
+                                    0x10000
+                                        + (((first as u32) - 0xd800) << 10)
+                                        + ((second as u32) - 0xdc00),
+                                ),
+                                4,
+                            ),
+                            _ => (None, 2),
+                        },
+                        _ => (char::from_u32(first as u32), 2),
+                    };
+
+                    i += len;
+
+                    if let Some(ch) = ch {
+                        let mut tmp = [0; 4];
+                        self.buffer
+                            .extend_from_slice(ch.encode_utf8(&mut tmp).as_bytes());
+                    } else if !self.invalid() {
+                        break;
+                    }
+                }
+            }
+
+            encoding @ (Encoding::Latin1 | Encoding::Windows1252) => {
+                while i < input.len() {
+                    let byte = input[i];
+                    i += 1;
+
+                    let ch = match byte {
+                        0x80..=0x9f if encoding == Encoding::Windows1252 => {
+                            WINDOWS1252[(byte - 0x80) as usize]
+                        }
+                        _ => byte as char,
+                    };
+
+                    if ch == '\0' && byte != 0 {
+                        if !self.invalid() {
+                            break;
+                        }
+                    } else {
+                        let mut tmp = [0; 4];
+                        self.buffer
+                            .extend_from_slice(ch.encode_utf8(&mut tmp).as_bytes());
+                    }
+                }
+            }
+        }
+
+        // Keep undecoded input for the next chunk
+        self.input.extend_from_slice(&input[i..]);
+        Ok(())
+    }
+}
+
+impl Read for Decoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.fill_buf()?;
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for Decoder {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos >= self.buffer.len() {
+            if let Some(error) = self.error.take() {
+                self.eof = true;
+                return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+            }
+
+            if self.eof {
+                break;
+            }
+
+            self.buffer.clear();
+            self.pos = 0;
+            self.decode()?;
+        }
+
+        Ok(&self.buffer[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+#[test]
+fn test_decoder() {
+    fn decode(input: &[u8], encoding: Option<Encoding>, replace: bool) -> Result<String, String> {
+        let mut decoder = Decoder::new(Box::new(io::Cursor::new(input.to_vec())), encoding);
+        decoder.set_replace(replace);
+
+        let mut s = String::new();
+        match decoder.read_to_string(&mut s) {
+            Ok(_) => Ok(s),
+            Err(err) => Err(format!("{} after {:?}", err, s)),
+        }
+    }
+
+    // Byte order mark detection
+    assert_eq!(
+        decode(b"\xef\xbb\xbfH\xc3\xa4", None, false),
+        Ok("Hä".into())
+    );
+    assert_eq!(decode(b"\xff\xfeH\0\xe4\0", None, false), Ok("Hä".into()));
+    assert_eq!(decode(b"\xfe\xff\0H\0\xe4", None, false), Ok("Hä".into()));
+    assert_eq!(decode(b"H", None, false), Ok("H".into()));
+
+    // UTF-16 with surrogate pairs
+    assert_eq!(
+        decode(b"=\xd8\0\xdeA\0", Some(Encoding::Utf16Le), false),
+        Ok("😀A".into())
+    );
+    assert_eq!(
+        decode(b"\xd8=\xde\0\0A", Some(Encoding::Utf16Be), false),
+        Ok("😀A".into())
+    );
+
+    // Single byte encodings
+    assert_eq!(
+        decode(b"\x80\xe4", Some(Encoding::Latin1), false),
+        Ok("\u{80}ä".into())
+    );
+    assert_eq!(
+        decode(b"\x80\xe4\x93", Some(Encoding::Windows1252), false),
+        Ok("€ä“".into())
+    );
+
+    // Decoding failures and replacement mode
+    assert_eq!(
+        decode(b"ab\xffcd", None, false),
+        Err("Invalid UTF-8 input after \"ab\"".into())
+    );
+    assert_eq!(decode(b"ab\xffcd", None, true), Ok("ab\u{fffd}cd".into()));
+    assert_eq!(
+        decode(b"A\0\0\xd8B\0", Some(Encoding::Utf16Le), false),
+        Err("Invalid UTF-16LE input after \"A\"".into())
+    );
+    assert_eq!(
+        decode(b"A\0B", Some(Encoding::Utf16Le), true),
+        Ok("A\u{fffd}".into())
+    );
+    assert_eq!(
+        decode(b"a\x81b", Some(Encoding::Windows1252), true),
+        Ok("a\u{fffd}b".into())
+    );
+}
+
+#[test]
+fn test_reader_encoding() {
+    use crate::reader::Reader;
+    use crate::value::Object;
+
+    let mut compiler = crate::Compiler::new(true);
+    compiler.compile_from_str("Word").unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let input = || Box::new(io::Cursor::new(b"Gr\xfc\xdfe\nFrom \xe0 la".to_vec()));
+
+    // Decoding failures are reported as errors, positioned where the input becomes undecodable
+    assert_eq!(
+        program
+            .run_from_reader(Reader::new(input()))
+            .map_err(|err| err.to_string()),
+        Err("Line 1, column 3: Invalid UTF-8 input".to_string())
+    );
+
+    assert_eq!(
+        program
+            .run_from_reader(Reader::new_decoding(input(), Some(Encoding::Latin1), false))
+            .unwrap()
+            .unwrap()
+            .repr(),
+        "(\"Grüße\", \"From\", \"à\", \"la\")"
+    );
+
+    assert_eq!(
+        program
+            .run_from_reader(Reader::new_decoding(input(), None, true))
+            .unwrap()
+            .unwrap()
+            .repr(),
+        "(\"Gr\", \"e\", \"From\", \"la\")"
+    );
+}
@@ -1,19 +1,59 @@
 //! Implementation of an error object that can occur during Tokay's program compilation or execution
 use crate::reader::Offset;
 use crate::value::{Object, Str};
+use crate::vm::Limit;
 extern crate self as tokay;
 use tokay_macros::tokay_function;
 
+/// Entry of a Tokay-level backtrace, collected while an error bubbles up through parselets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub parselet: String,       // Name of the parselet
+    pub source: Option<Offset>, // Source position inside the parselet where the error passed
+    pub input: Offset,          // Input position where the parselet started reading
+}
+
+impl std::fmt::Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Formats an offset as a position, with its source name when available
+        fn position(offset: &Offset) -> String {
+            if let Some(source) = &offset.source {
+                format!("{}:{}:{}", source, offset.row, offset.col)
+            } else {
+                format!("line {}, column {}", offset.row, offset.col)
+            }
+        }
+
+        write!(f, "in {}", self.parselet)?;
+
+        if let Some(source) = &self.source {
+            write!(f, " at {}", position(source))?;
+        }
+
+        write!(f, ", reading from {}", position(&self.input))
+    }
+}
+
+// Number of innermost and outermost backtrace entries displayed from deep backtraces
+const TRACE_ENDS: usize = 10;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Error {
     pub offset: Option<Offset>,
     pub message: String,
+    pub trace: Vec<Trace>,    // Tokay-level backtrace, innermost parselet first
+    pub limit: Option<Limit>, // Execution limit which ended the run
 }
 
 impl Error {
     /// Creates a new Error object with a message.
     pub fn new(offset: Option<Offset>, message: String) -> Error {
-        Error { offset, message }
+        Error {
+            offset,
+            message,
+            trace: Vec::new(),
+            limit: None,
+        }
     }
 
     /// Attaches position information to an error message when not already present
@@ -26,15 +66,44 @@ impl Error {
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(offset) = self.offset {
-            write!(
-                f,
-                "Line {}, column {}: {}",
-                offset.row, offset.col, self.message
-            )
+        if let Some(offset) = &self.offset {
+            if let Some(source) = &offset.source {
+                write!(
+                    f,
+                    "{}:{}:{}: {}",
+                    source, offset.row, offset.col, self.message
+                )?;
+            } else {
+                write!(
+                    f,
+                    "Line {}, column {}: {}",
+                    offset.row, offset.col, self.message
+                )?;
+            }
         } else {
-            write!(f, "{}", self.message)
+            write!(f, "{}", self.message)?;
+        }
+
+        // The alternate format "{:#}" includes the backtrace, with the middle of deep ones elided
+        if f.alternate() {
+            if self.trace.len() > 2 * TRACE_ENDS {
+                for trace in &self.trace[..TRACE_ENDS] {
+                    write!(f, "\n  {}", trace)?;
+                }
+
+                write!(f, "\n  ... {} more ...", self.trace.len() - 2 * TRACE_ENDS)?;
+
+                for trace in &self.trace[self.trace.len() - TRACE_ENDS..] {
+                    write!(f, "\n  {}", trace)?;
+                }
+            } else {
+                for trace in &self.trace {
+                    write!(f, "\n  {}", trace)?;
+                }
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -44,6 +113,15 @@ impl From<String> for Error {
     }
 }
 
+impl From<Limit> for Error {
+    fn from(limit: Limit) -> Self {
+        Error {
+            limit: Some(limit),
+            ..Error::new(None, limit.to_string())
+        }
+    }
+}
+
 impl From<&str> for Error {
     fn from(error: &str) -> Self {
         error.to_string().into()
@@ -76,3 +154,58 @@ fn test_error() {
         Err("Line 1, column 4: Error!".to_string())
     );
 }
+
+#[test]
+fn test_error_source() {
+    use crate::reader::Reader;
+    use std::io::Cursor;
+
+    // Compile-time errors
+    let mut reader = Reader::new(Box::new(Cursor::new("x = 1\ny = (1\n")));
+    reader.set_source("prog.tok");
+
+    let mut compiler = crate::Compiler::new(true);
+    let errors = compiler.compile(reader).unwrap_err();
+    assert_eq!(errors[0].to_string(), "prog.tok:3:1: Expecting \")\"");
+
+    // Runtime errors
+    let mut compiler = crate::Compiler::new(true);
+    compiler.compile_from_str("'a'+ error(\"Error!\")").unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let mut reader = Reader::new(Box::new(Cursor::new("xaaa")));
+    reader.set_source("input.txt");
+
+    assert_eq!(
+        program.run_from_reader(reader).unwrap_err().to_string(),
+        "input.txt:1:5: Error!"
+    );
+}
+
+#[test]
+fn test_error_trace() {
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str("Inner : @{ Int error(\"Error!\") }\nOuter : @{ 'x' Inner }\nOuter")
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let error = program.run_from_str("ax5").unwrap_err();
+
+    assert_eq!(
+        error
+            .trace
+            .iter()
+            .map(|trace| trace.parselet.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Inner", "Outer", "__main__"]
+    );
+
+    assert_eq!(
+        format!("{:#}", error),
+        "Line 1, column 4: Error!\n  \
+        in Inner at line 1, column 16, reading from line 1, column 3\n  \
+        in Outer at line 2, column 16, reading from line 1, column 2\n  \
+        in __main__ at line 3, column 1, reading from line 1, column 2"
+    );
+}
@@ -12,6 +12,7 @@
 mod _builtins; // Generated builtin registry
 pub mod builtin;
 pub mod compiler;
+pub mod encoding;
 pub mod error;
 pub mod reader;
 pub mod repl;
@@ -3,8 +3,11 @@ use clap::Parser;
 use rustyline;
 use std::cell::RefCell;
 use std::fs::{self, File};
+use std::path::PathBuf;
 use tokay::compiler::Compiler;
+use tokay::encoding::Encoding;
 use tokay::repl::{repl, Stream};
+use tokay::vm::{Memoization, Profile, Program, Runtime};
 use tokay::Object;
 use tokay::Reader;
 
@@ -26,7 +29,7 @@ fn print_version() {
 
 PROGRAM and INPUT are directly used as input strings in case no file with the
 given name exists. Use '-f' to disable this behavior. Specify '-' to use stdin
-as input file.
+as input file. PROGRAM files written by '--save' are loaded without compiling.
 
 When PROGRAM was not specified, {bin} turns into an interactive REPL.
 
@@ -50,6 +53,36 @@ struct Opts {
     #[clap(short, long, action)]
     repl: bool,
 
+    /// Encoding of INPUT, e.g. utf-8, utf-16le, utf-16be, latin1 or windows-1252.
+    /// Use 'auto' to detect it from a byte order mark.
+    #[clap(short, long, value_parser)]
+    encoding: Option<String>,
+
+    /// Replace INPUT that can't be decoded by U+FFFD instead of failing.
+    #[clap(long, action)]
+    replace: bool,
+
+    /// Memoization policy: 'full', 'linear' (only left-recursive parselets),
+    /// or the maximum number of memo entries.
+    #[clap(long, value_parser, value_name = "POLICY")]
+    memo: Option<String>,
+
+    /// Profile the run, and print statistics per parselet when finished.
+    #[clap(long, action)]
+    profile: bool,
+
+    /// Write profiled call stacks in folded format to FILE, e.g. for flamegraph.pl.
+    #[clap(long, value_parser, value_name = "FILE")]
+    profile_stacks: Option<String>,
+
+    /// Add DIR to the search path for imported modules.
+    #[clap(short = 'I', long, value_parser, value_name = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// Compile PROGRAM and save it as bytecode to FILE, instead of running it.
+    #[clap(short, long, value_parser, value_name = "FILE")]
+    save: Option<String>,
+
     /// Sets the debug level.
     #[clap(short, long, action = clap::ArgAction::Count)]
     debug: u8,
@@ -75,6 +108,31 @@ fn main() {
         std::process::exit(0);
     }
 
+    // Input encoding, where None stands for detection.
+    let encoding = match opts.encoding.as_deref() {
+        None | Some("auto") => None,
+        Some(name) => match Encoding::from_name(name) {
+            Some(encoding) => Some(encoding),
+            None => {
+                eprintln!("Unknown encoding '{}'", name);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    // Memoization policy
+    let memoization = match opts.memo.as_deref() {
+        None | Some("full") => Memoization::Full,
+        Some("linear") => Memoization::Linear,
+        Some(limit) => match limit.parse::<usize>() {
+            Ok(limit) => Memoization::Bounded(limit),
+            Err(_) => {
+                eprintln!("Invalid memoization policy '{}'", limit);
+                std::process::exit(1);
+            }
+        },
+    };
+
     // Read program, either from stdin, file or direct string.
     let mut program: Option<Stream> = None;
 
@@ -85,7 +143,7 @@ fn main() {
             if let Some(meta) = fs::metadata(prog).ok() {
                 if !meta.is_dir() {
                     if let Ok(file) = File::open(prog) {
-                        program = Some(Stream::File(file));
+                        program = Some(Stream::File(prog.to_string(), file));
                     }
                 }
             }
@@ -108,7 +166,10 @@ fn main() {
         if filename == "-" && !opts.files {
             streams.push((filename, RefCell::new(Stream::Stdin)))
         } else if let Ok(file) = File::open(filename) {
-            streams.push((filename, RefCell::new(Stream::File(file))))
+            streams.push((
+                filename,
+                RefCell::new(Stream::File(filename.to_string(), file)),
+            ))
         } else if !opts.files {
             streams.push((filename, RefCell::new(Stream::String(filename.to_string()))))
         } else {
@@ -118,87 +179,161 @@ fn main() {
     }
 
     if let Some(mut program) = program {
-        let mut compiler = Compiler::new(true);
-
-        if compiler.compile(program.get_reader()).is_ok() {
-            if let Ok(program) = compiler.finalize() {
-                // In case no stream but a program is specified, use stdin as input stream.
-                if streams.len() == 0 {
-                    // Run program in its own REPL?
-                    if opts.repl {
-                        let mut readline = rustyline::Editor::<()>::new();
-                        readline.load_history(".tokayrepl").ok();
-
-                        loop {
-                            let code = match readline.readline("<<< ") {
-                                Err(rustyline::error::ReadlineError::Interrupted)
-                                | Err(rustyline::error::ReadlineError::Eof) => break,
-                                Err(err) => {
-                                    eprintln!("Error {:?}", err);
-                                    break;
-                                }
+        // Bytecode is loaded directly, any other program is compiled.
+        let bytecode = match &program {
+            Stream::File(filename, _) => fs::read(filename)
+                .ok()
+                .filter(|bytes| Program::is_bytecode(bytes)),
+            _ => None,
+        };
+
+        let program = if let Some(bytecode) = bytecode {
+            match Program::from_bytecode(&bytecode) {
+                Ok(program) => Some(program),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            }
+        } else {
+            let mut compiler = Compiler::new(true);
+            compiler.search_path.extend(opts.include.iter().cloned());
+
+            if compiler.compile(program.get_reader()).is_ok() {
+                compiler.finalize().ok()
+            } else {
+                None
+            }
+        };
+
+        if let Some(program) = program {
+            // Save program as bytecode and exit?
+            if let Some(filename) = &opts.save {
+                match program.to_bytecode() {
+                    Ok(bytecode) => {
+                        if let Err(err) = fs::write(filename, bytecode) {
+                            eprintln!("Can't write bytecode to '{}': {}", filename, err);
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        std::process::exit(1);
+                    }
+                }
 
-                                Ok(code) => code,
-                            };
+                std::process::exit(0);
+            }
 
-                            // Stop when program is empty.
-                            if code.trim().is_empty() {
-                                continue;
+            // In case no stream but a program is specified, use stdin as input stream.
+            if streams.len() == 0 {
+                // Run program in its own REPL?
+                if opts.repl {
+                    let mut readline = rustyline::Editor::<()>::new();
+                    readline.load_history(".tokayrepl").ok();
+
+                    loop {
+                        let code = match readline.readline("<<< ") {
+                            Err(rustyline::error::ReadlineError::Interrupted)
+                            | Err(rustyline::error::ReadlineError::Eof) => break,
+                            Err(err) => {
+                                eprintln!("Error {:?}", err);
+                                break;
                             }
 
-                            readline.add_history_entry(code.as_str());
+                            Ok(code) => code,
+                        };
 
-                            match program
-                                .run_from_reader(Reader::new(Box::new(std::io::Cursor::new(code))))
-                            {
-                                Ok(None) => {
-                                    if streams.len() > 1 {
-                                        print!("\n")
-                                    }
+                        // Stop when program is empty.
+                        if code.trim().is_empty() {
+                            continue;
+                        }
+
+                        readline.add_history_entry(code.as_str());
+
+                        match program
+                            .run_from_reader(Reader::new(Box::new(std::io::Cursor::new(code))))
+                        {
+                            Ok(None) => {
+                                if streams.len() > 1 {
+                                    print!("\n")
                                 }
-                                Ok(Some(value)) => println!("{}", value.to_string()),
-                                Err(error) => eprintln!("{}", error),
                             }
+                            Ok(Some(value)) => println!("{}", value.to_string()),
+                            Err(error) => eprintln!("{:#}", error),
                         }
-
-                        readline.save_history(".tokayrepl").unwrap();
-                        std::process::exit(0);
                     }
 
-                    streams.push((
-                        "",
-                        // When program's main is consuming, read from stdin
-                        if program.main().is_consuming() {
-                            RefCell::new(Stream::Stdin)
-                        }
-                        // otherwise just work on an empty input
-                        else {
-                            RefCell::new(Stream::String("".to_string()))
-                        },
-                    ));
+                    readline.save_history(".tokayrepl").unwrap();
+                    std::process::exit(0);
                 }
 
-                if opts.repl {
-                    eprintln!("REPL-mode not allowed in combination with provided INPUT");
-                    std::process::exit(1);
+                streams.push((
+                    "",
+                    // When program's main is consuming, read from stdin
+                    if program.main().is_consuming() {
+                        RefCell::new(Stream::Stdin)
+                    }
+                    // otherwise just work on an empty input
+                    else {
+                        RefCell::new(Stream::String("".to_string()))
+                    },
+                ));
+            }
+
+            if opts.repl {
+                eprintln!("REPL-mode not allowed in combination with provided INPUT");
+                std::process::exit(1);
+            }
+
+            for (name, stream) in &streams {
+                let mut reader = if opts.encoding.is_some() || opts.replace {
+                    stream
+                        .borrow_mut()
+                        .get_decoding_reader(encoding, opts.replace)
+                } else {
+                    stream.borrow_mut().get_reader()
+                };
+
+                let mut runtime = Runtime::new(&program, &mut reader);
+                runtime.memoization = memoization;
+
+                if opts.profile || opts.profile_stacks.is_some() {
+                    runtime.profile = Some(Profile::new());
                 }
 
-                for (name, stream) in &streams {
-                    let ret = program.run_from_reader(stream.borrow_mut().get_reader());
+                let ret = program.run(&mut runtime);
 
-                    if streams.len() > 1 {
-                        print!("{}: ", name);
+                // Report profiling results
+                if let Some(profile) = &runtime.profile {
+                    if opts.profile {
+                        eprint!("{}", profile.summary());
                     }
 
-                    match ret {
-                        Ok(None) => {
-                            if streams.len() > 1 {
-                                print!("\n")
-                            }
+                    if let Some(filename) = &opts.profile_stacks {
+                        if let Err(err) = fs::write(filename, profile.folded()) {
+                            eprintln!("Can't write profile to '{}': {}", filename, err);
+                        }
+                    }
+                }
+
+                // Errors recovered from during the run are reported as well
+                for error in &runtime.errors {
+                    eprintln!("{}", error);
+                }
+
+                if streams.len() > 1 {
+                    print!("{}: ", name);
+                }
+
+                match ret {
+                    Ok(None) => {
+                        if streams.len() > 1 {
+                            print!("\n")
                         }
-                        Ok(Some(value)) => println!("{}", value.to_string()),
-                        Err(error) => eprintln!("{}", error),
                     }
+                    Ok(Some(value)) => println!("{}", value.to_string()),
+                    Err(error) => eprintln!("{:#}", error),
                 }
             }
         }
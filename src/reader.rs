@@ -1,25 +1,49 @@
 //! Universal low-level interface to let Tokay read input from different sources.
+use crate::encoding::{Decoder, Encoding};
+use crate::error::Error;
 use num_parse::PeekableIterator;
+use std::borrow::Cow;
 use std::io::prelude::*;
+use std::rc::Rc;
 
-/// Position inside a reader, with row and column counting.
-#[derive(Debug, Clone, Copy, PartialEq)]
+/** Position inside a reader, with row and column counting, and the name of the source when known.
+
+The offset also holds the indentation stack, so it is restored on backtracking. */
+#[derive(Debug, Clone, PartialEq)]
 pub struct Offset {
-    // todo: Hold source filename information as well in the future?
+    pub source: Option<Rc<str>>,
     pub offset: usize,
     pub row: u32,
     pub col: u32,
+    pub indent: Option<Rc<Indent>>,
+}
+
+/// Indentation level, as an immutable stack shared among offsets.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Indent {
+    pub width: usize,
+    pub parent: Option<Rc<Indent>>,
 }
 
 pub type Range = std::ops::Range<usize>;
 
-// Abstraction of a buffered Reader with internal buffering, offset counting and clean-up.
+/** Abstraction of a buffered Reader with internal buffering, offset counting and clean-up.
+
+The reader either works in text mode, where the input is expected to be UTF-8, or in binary mode,
+where every byte of the input is read as one character (as in ISO-8859-1). In both modes, offsets
+are counted in bytes. */
 pub struct Reader {
     reader: Box<dyn BufRead>, // Reader object to read from
-    buffer: String,           // Internal buffer
+    buffer: Vec<u8>,          // Internal buffer
+    base: usize,              // Offset of the first byte in buffer
+    cut: usize,               // Offset of the last cut, input before can't be read again
+    binary: bool,             // Binary mode
     peeked: char,             // Currently peeked char
     offset: Offset,           // Current offset
     eof: bool,                // EOF marker
+    error: Option<Error>,     // Error that caused EOF
+    incremental: bool,        // Input is fed incrementally
+    starved: bool,            // Ran out of fed input
 }
 
 impl Reader {
@@ -27,38 +51,240 @@ impl Reader {
     pub fn new(reader: Box<dyn BufRead>) -> Self {
         Self {
             reader,
-            buffer: String::with_capacity(1024), //fixme: Modifyable capacity?
+            buffer: Vec::with_capacity(1024), //fixme: Modifyable capacity?
+            base: 0,
+            cut: 0,
+            binary: false,
             peeked: ' ',
             offset: Offset {
+                source: None,
                 offset: 0,
                 row: 1,
                 col: 1,
+                indent: None,
             },
             eof: false,
+            error: None,
+            incremental: false,
+            starved: false,
         }
     }
 
-    /// Internal function for reading a line.
+    /** Creates a new reader which is incrementally fed with input.
+
+    Input is appended by Reader::feed(), and the reader reports to be starved when it runs out of
+    fed input. Reader::finish() marks the end of input. */
+    pub fn new_incremental() -> Self {
+        let mut reader = Self::new(Box::new(std::io::empty()));
+        reader.incremental = true;
+        reader
+    }
+
+    /// Appends input to an incremental reader.
+    pub fn feed(&mut self, input: &str) {
+        assert!(self.incremental, "Reader is not incremental");
+        self.buffer.extend_from_slice(input.as_bytes());
+        self.starved = false;
+    }
+
+    /// Marks the end of input of an incremental reader.
+    pub fn finish(&mut self) {
+        self.incremental = false;
+        self.starved = false;
+    }
+
+    /// Returns true when an incremental reader ran out of fed input.
+    pub fn is_starved(&self) -> bool {
+        self.starved
+    }
+
+    /// Resets the starved state, before further input is read.
+    pub(crate) fn clear_starved(&mut self) {
+        self.starved = false;
+    }
+
+    /// Creates a new reader in binary mode, where any byte is read as one character.
+    pub fn new_binary(reader: Box<dyn BufRead>) -> Self {
+        let mut reader = Self::new(reader);
+        reader.binary = true;
+        reader
+    }
+
+    /** Creates a new reader on input in the given encoding, which is transcoded into UTF-8.
+
+    When no encoding is given, it is detected from a byte order mark, falling back to UTF-8.
+    In replacement mode, undecodable input is replaced by U+FFFD instead of causing an error. */
+    pub fn new_decoding(
+        reader: Box<dyn BufRead>,
+        encoding: Option<Encoding>,
+        replace: bool,
+    ) -> Self {
+        let mut decoder = Decoder::new(reader, encoding);
+        decoder.set_replace(replace);
+        Self::new(Box::new(decoder))
+    }
+
+    /// Sets the name of the source the reader reads from, e.g. a filename.
+    pub fn set_source(&mut self, source: &str) {
+        self.offset.source = Some(Rc::from(source));
+    }
+
+    /// Returns the name of the source the reader reads from.
+    pub fn source(&self) -> Option<&str> {
+        self.offset.source.as_deref()
+    }
+
+    /// Returns true when the reader runs in binary mode.
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    /// Internal function for reading a line, or a chunk of data in binary mode.
     fn read_line(&mut self) -> Option<usize> {
-        if let Ok(n) = self.reader.read_line(&mut self.buffer) {
-            if n == 0 {
+        // Incremental readers have to wait for more input
+        if self.incremental {
+            self.starved = true;
+            return None;
+        }
+
+        let start = self.buffer.len();
+
+        let res = if self.binary {
+            self.reader.fill_buf().map(|chunk| {
+                self.buffer.extend_from_slice(chunk);
+                chunk.len()
+            })
+        } else {
+            self.reader.read_until(b'\n', &mut self.buffer)
+        };
+
+        match res {
+            Ok(0) => {
                 self.eof = true;
-                return None;
+                None
             }
+            Err(err) => {
+                // Bytes already read in text mode must be valid UTF-8 as well
+                if !self.binary {
+                    self.validate(start);
+                }
 
-            Some(n)
-        } else {
-            self.eof = true;
-            None
+                if self.error.is_none() {
+                    self.fail(err.to_string());
+                }
+
+                None
+            }
+            Ok(n) => {
+                if self.binary {
+                    self.reader.consume(n);
+                }
+                // In text mode, input must be valid UTF-8
+                else if !self.validate(start) {
+                    return None;
+                }
+
+                Some(n)
+            }
+        }
+    }
+
+    /// Internal function to check buffered input from start for valid UTF-8, and fail otherwise.
+    fn validate(&mut self, start: usize) -> bool {
+        if let Err(err) = std::str::from_utf8(&self.buffer[start..]) {
+            self.buffer.truncate(start + err.valid_up_to());
+            self.fail("Invalid UTF-8 input".to_string());
+            return false;
+        }
+
+        true
+    }
+
+    /// Internal function to stop reading with an error positioned at the end of the buffer.
+    fn fail(&mut self, msg: String) {
+        let mut offset = self.offset.clone();
+
+        for ch in self
+            .get(&(offset.offset..self.base + self.buffer.len()))
+            .chars()
+        {
+            if ch == '\n' {
+                offset.row += 1;
+                offset.col = 1;
+            } else {
+                offset.col += 1;
+            }
         }
+
+        offset.offset = self.base + self.buffer.len();
+
+        self.error = Some(Error::new(Some(offset), msg));
+        self.eof = true;
+    }
+
+    /// Takes the error which caused the reader to stop reading, if any.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.error.take()
+    }
+
+    /// Internal function to decode the character and its length in bytes at the current offset.
+    fn decode(&self) -> Option<(char, usize)> {
+        let bytes = &self.buffer[self.offset.offset - self.base..];
+        let first = *bytes.first()?;
+
+        if self.binary || first < 0x80 {
+            return Some((first as char, 1));
+        }
+
+        let len = match first {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+
+        // Offsets in the middle of a UTF-8 sequence fall back to the plain byte.
+        match bytes.get(..len).map(std::str::from_utf8) {
+            Some(Ok(s)) => Some((s.chars().next().unwrap(), len)),
+            _ => Some((first as char, 1)),
+        }
+    }
+
+    /** Internal function to advance the offset by a consumed character of len bytes.
+
+    Line breaks are "\n", "\r\n" and "\r", like in Tokay's parser. */
+    fn advance(&mut self, ch: char, len: usize) {
+        let cr = self.offset.offset > self.base
+            && self.buffer[self.offset.offset - self.base - 1] == b'\r';
+        self.offset.offset += len;
+
+        match ch {
+            '\n' if cr => {}
+            '\n' | '\r' => {
+                self.offset.row += 1;
+                self.offset.col = 1;
+            }
+            _ => self.offset.col += 1,
+        }
+    }
+
+    /// Make sure that at least n bytes from the current offset are buffered.
+    fn fill(&mut self, n: usize) -> bool {
+        while self.base + self.buffer.len() < self.offset.offset + n {
+            if self.eof || self.read_line().is_none() {
+                return false;
+            }
+        }
+
+        true
     }
 
     pub fn tell(&self) -> Offset {
-        self.offset
+        self.offset.clone()
     }
 
     pub fn eof(&mut self) -> bool {
-        if self.buffer[self.offset.offset..].chars().next().is_some() {
+        if self.offset.offset < self.base + self.buffer.len() {
             false
         } else {
             if !self.eof {
@@ -75,16 +301,16 @@ impl Reader {
 
     /// Capture last length characters.
     pub fn capture_last(&self, mut length: usize) -> Range {
-        if length > self.offset.offset {
-            length = self.offset.offset;
+        if length > self.offset.offset - self.base {
+            length = self.offset.offset - self.base;
         }
 
         self.offset.offset - length..self.offset.offset
     }
 
-    // Capture all characters from start to current offset.
+    // Capture all characters from start to current offset, but not before a cut.
     pub fn capture_from(&self, start: &Offset) -> Range {
-        let mut start = start.offset;
+        let mut start = start.offset.max(self.cut);
 
         if start > self.offset.offset {
             start = self.offset.offset;
@@ -94,18 +320,67 @@ impl Reader {
     }
 
     pub fn print(&self, start: usize) {
-        println!("{:?}", &self.buffer[start..self.offset.offset])
+        println!("{:?}", self.get(&(start..self.offset.offset)))
+    }
+
+    /** Get slice from range
+
+    In binary mode, or when the range doesn't contain valid UTF-8, every byte is returned as one
+    character. */
+    pub fn get(&self, range: &Range) -> Cow<'_, str> {
+        let bytes = self.get_bytes(range);
+
+        if !self.binary {
+            if let Ok(s) = std::str::from_utf8(bytes) {
+                return Cow::Borrowed(s);
+            }
+        }
+
+        Cow::Owned(bytes.iter().map(|b| *b as char).collect())
     }
 
-    /// Get slice from range
-    pub fn get(&self, range: &Range) -> &str {
-        &self.buffer[range.start..range.end]
+    /// Get raw bytes from range
+    pub fn get_bytes(&self, range: &Range) -> &[u8] {
+        &self.buffer[range.start - self.base..range.end - self.base]
     }
 
     /// Commits current input buffer and removes cached content
     pub fn commit(&mut self) {
-        self.buffer.drain(0..self.offset.offset);
+        self.buffer.drain(0..self.offset.offset - self.base);
         self.offset.offset = 0;
+        self.base = 0;
+        self.cut = 0;
+    }
+
+    /** Cuts the input buffer at the current offset.
+
+    Input before the current offset is removed from the buffer, but unlike Reader::commit(),
+    offsets keep counting from where they are. Input before the cut can't be read again.
+
+    While input is fed incrementally, the buffer is kept until it is committed, so that an
+    iteration of main which runs out of input can be rewound and repeated. */
+    pub fn cut(&mut self) {
+        if !self.incremental {
+            self.buffer.drain(0..self.offset.offset - self.base);
+            self.base = self.offset.offset;
+        }
+
+        self.cut = self.offset.offset;
+    }
+
+    /** Rewinds to an offset, also before the last cut.
+
+    This is only possible for input which is still buffered, e.g. to repeat an iteration of main
+    which ran out of incrementally fed input. */
+    pub(crate) fn rewind(&mut self, offset: Offset) {
+        assert!(offset.offset >= self.base, "Cannot rewind before buffer");
+        self.cut = self.cut.min(offset.offset);
+        self.offset = offset;
+    }
+
+    /// Returns the position of the last cut, input before it can't be read again.
+    pub fn base(&self) -> usize {
+        self.cut
     }
 
     /// Take one character accepted by callback
@@ -123,7 +398,7 @@ impl Reader {
     }
 
     /// Read while conditional callback accepts characters
-    pub fn span<F>(&mut self, accept: F) -> Option<&str>
+    pub fn span<F>(&mut self, accept: F) -> Option<Cow<'_, str>>
     where
         F: Fn(char) -> bool + Copy,
     {
@@ -132,11 +407,81 @@ impl Reader {
         while self.once(accept).is_some() {}
 
         if start < self.offset.offset {
-            Some(&self.buffer[start..self.offset.offset])
+            Some(self.get(&(start..self.offset.offset)))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the width of the current indentation level.
+    pub fn indent(&self) -> usize {
+        self.offset.indent.as_ref().map_or(0, |indent| indent.width)
+    }
+
+    /// Pushes a new indentation level.
+    pub fn push_indent(&mut self, width: usize) {
+        self.offset.indent = Some(Rc::new(Indent {
+            width,
+            parent: self.offset.indent.take(),
+        }));
+    }
+
+    /// Pops the current indentation level; Returns false when there is none.
+    pub fn pop_indent(&mut self) -> bool {
+        match self.offset.indent.take() {
+            Some(indent) => {
+                self.offset.indent = indent.parent.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the input buffered from the current offset.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[self.offset.offset - self.base..]
+    }
+
+    /// Reads further input into the buffer; Returns false when no more input is available.
+    pub fn extend(&mut self) -> bool {
+        !self.eof && self.read_line().is_some()
+    }
+
+    /// Advances by characters until the given offset in the buffer is reached.
+    pub fn advance_to(&mut self, offset: usize) {
+        while self.offset.offset < offset && self.next().is_some() {}
+    }
+
+    /// Peek the byte at the current offset, regardless of the reader mode.
+    pub fn peek_byte(&mut self) -> Option<u8> {
+        if self.fill(1) {
+            Some(self.buffer[self.offset.offset - self.base])
         } else {
             None
         }
     }
+
+    /// Take the byte at the current offset, regardless of the reader mode.
+    pub fn next_byte(&mut self) -> Option<u8> {
+        let byte = self.peek_byte()?;
+        self.advance(byte as char, 1);
+        Some(byte)
+    }
+
+    /// Take exactly n bytes from the current offset, regardless of the reader mode.
+    pub fn take_bytes(&mut self, n: usize) -> Option<Range> {
+        if !self.fill(n) {
+            return None;
+        }
+
+        let start = self.offset.clone();
+
+        for _ in 0..n {
+            self.next_byte();
+        }
+
+        Some(self.capture_from(&start))
+    }
 }
 
 impl Iterator for Reader {
@@ -144,24 +489,14 @@ impl Iterator for Reader {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if let Some(ch) = self.buffer[self.offset.offset..].chars().next() {
-                self.offset.offset += ch.len_utf8();
-
-                if ch == '\n' {
-                    self.offset.row += 1;
-                    self.offset.col = 1;
-                } else {
-                    self.offset.col += 1;
-                }
-
+            if let Some((ch, len)) = self.decode() {
+                self.advance(ch, len);
                 return Some(ch);
             }
 
-            if self.eof {
+            if self.eof || self.read_line().is_none() {
                 return None;
             }
-
-            self.read_line();
         }
     }
 }
@@ -169,16 +504,14 @@ impl Iterator for Reader {
 impl PeekableIterator for Reader {
     fn peek(&mut self) -> Option<&Self::Item> {
         loop {
-            if let Some(ch) = self.buffer[self.offset.offset..].chars().next() {
+            if let Some((ch, _)) = self.decode() {
                 self.peeked = ch;
                 return Some(&self.peeked);
             }
 
-            if self.eof {
+            if self.eof || self.read_line().is_none() {
                 return None;
             }
-
-            self.read_line();
         }
     }
 }
@@ -2,12 +2,13 @@
 
 use std::cell::RefCell;
 use std::fs::File;
-use std::io::{self, BufReader, Seek};
+use std::io::{self, BufRead, BufReader, Seek};
 
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
 use crate::compiler::Compiler;
+use crate::encoding::Encoding;
 use crate::reader::Reader;
 use crate::value::{Object, RefValue};
 use crate::vm::Runtime;
@@ -16,21 +17,46 @@ use crate::vm::Runtime;
 #[derive(Debug)]
 pub enum Stream {
     String(String),
-    File(File),
+    File(String, File),
     Stdin,
 }
 
 impl Stream {
     pub fn get_reader(&mut self) -> Reader {
+        let (input, source) = self.open();
+        let mut reader = Reader::new(input);
+
+        if let Some(source) = source {
+            reader.set_source(&source);
+        }
+
+        reader
+    }
+
+    /// Get a reader transcoding the stream from the given encoding, see Reader::new_decoding().
+    pub fn get_decoding_reader(&mut self, encoding: Option<Encoding>, replace: bool) -> Reader {
+        let (input, source) = self.open();
+        let mut reader = Reader::new_decoding(input, encoding, replace);
+
+        if let Some(source) = source {
+            reader.set_source(&source);
+        }
+
+        reader
+    }
+
+    // Internal function to open the stream from its beginning, with the name of its source.
+    fn open(&mut self) -> (Box<dyn BufRead>, Option<String>) {
         match self {
-            Stream::String(string) => Reader::new(Box::new(io::Cursor::new(string.clone()))),
-            Stream::File(file) => {
+            Stream::String(string) => (Box::new(io::Cursor::new(string.clone())), None),
+            Stream::File(filename, file) => {
                 let mut file = file.try_clone().expect("File cannot be cloned?");
                 file.seek(std::io::SeekFrom::Start(0))
                     .expect("Unable to seek to file's starting position");
-                Reader::new(Box::new(BufReader::new(file)))
+
+                (Box::new(BufReader::new(file)), Some(filename.clone()))
             }
-            Stream::Stdin => Reader::new(Box::new(BufReader::new(io::stdin()))),
+            Stream::Stdin => (Box::new(BufReader::new(io::stdin())), None),
         }
     }
 }
@@ -95,6 +121,10 @@ pub fn repl(streams: Vec<(&str, RefCell<Stream>)>) {
 
                             let ret = program.run(&mut runtime);
 
+                            for error in &runtime.errors {
+                                println!("{}", error);
+                            }
+
                             if streams.len() > 1 {
                                 print!("{}: ", name);
                             }
@@ -106,7 +136,7 @@ pub fn repl(streams: Vec<(&str, RefCell<Stream>)>) {
                                     }
                                 }
                                 Ok(Some(value)) => println!("{}", value.to_string()),
-                                Err(error) => println!("{}", error),
+                                Err(error) => println!("{:#}", error),
                             }
 
                             globals = runtime.save_stack();
@@ -541,6 +541,121 @@ fn token_modifiers() {
     // todo: more token tests, please!
 }
 
+#[test]
+// Test for case-insensitive tokens
+fn token_ignore_case() {
+    let s = "SELECT a; Select b; sel c";
+
+    // Touch and match keep the original text
+    assert_eq!(run("'select'i _ Word", s), Ok(Some(value![["a", "b"]])));
+    assert_eq!(
+        run("''select''i", s),
+        Ok(Some(value![["SELECT", "Select"]]))
+    );
+
+    // Character classes, also negated and with modifiers
+    assert_eq!(run("[a-c]i+", "ABCDabcx"), Ok(Some(value![["ABC", "abc"]])));
+    assert_eq!(run("[^a-c]i+", "ABCDabcx"), Ok(Some(value![["D", "x"]])));
+    assert_eq!(run("[k]i", "kKK"), Ok(Some(value![["k", "K", "K"]])));
+}
+
+#[test]
+// Test for Unicode property classes and class set operations
+fn token_unicode_property() {
+    let s = "Tokay λόγος Ωmega 42";
+
+    // Properties in character classes, also negated
+    assert_eq!(run("[\\p{Greek}]+", s), Ok(Some(value![["λόγος", "Ω"]])));
+    assert_eq!(run("[\\P{L}--\\p{Zs}]+", s), Ok(Some(value!["42"])));
+
+    // Named builtins
+    assert_eq!(run("Lu", s), Ok(Some(value![["T", "Ω"]])));
+    assert_eq!(run("Nds", s), Ok(Some(value!["42"])));
+    assert_eq!(
+        run("Lo", s),
+        Err("Line 1, column 1: Call to unresolved symbol 'Lo'".to_string())
+    );
+    assert_eq!(
+        run("AsciiWhitespaces", "a \t\nb"),
+        Ok(Some(value![" \t\n"]))
+    );
+
+    // Set operations: intersection and difference
+    assert_eq!(
+        run("[\\p{L}--\\p{Latin}]+", s),
+        Ok(Some(value![["λόγος", "Ω"]]))
+    );
+    assert_eq!(
+        run("[a-z--aeiou]+", "tokay"),
+        Ok(Some(value![["t", "k", "y"]]))
+    );
+    assert_eq!(run("[\\p{Lu}&&\\p{Latin}]", s), Ok(Some(value!["T"])));
+    assert_eq!(run("[+--]+", "a+-b"), Ok(Some(value!["+-"])));
+
+    // Breaking: "--" inside a class is a difference, so a range up to '-' must escape it
+    assert_eq!(run("[+--/]+", "a+,-/b"), Ok(Some(value!["+"])));
+    assert_eq!(run("[+-\\-/]+", "a+,-/b"), Ok(Some(value!["+,-/"])));
+
+    // Breaking: "&&" inside a class is an intersection, so a literal "&&" must be escaped
+    assert_eq!(run("[a&&b]+", "a&b"), Ok(None));
+    assert_eq!(run("[a\\&\\&b]+", "a&b"), Ok(Some(value!["a&b"])));
+    assert_eq!(run("[a&b]+", "a&b"), Ok(Some(value!["a&b"])));
+
+    // Unknown properties are reported
+    assert_eq!(
+        run("[\\p{Klingon}]", s),
+        Err("Line 1, column 1: Unknown Unicode property 'Klingon'".to_string())
+    );
+}
+
+#[test]
+// Test for indentation tokens
+fn token_indentation() {
+    testcase("tests/test_indent.tok");
+
+    assert_eq!(
+        run(
+            "Block : @{ Indent Word '\\n' {Samedent Word '\\n'}* Dedent }\nSamedent Word '\\n' Block?",
+            "a\n    b\n  c\n"
+        ),
+        Err("Line 3, column 3: Dedent doesn't match any outer indentation level".to_string())
+    );
+}
+
+#[test]
+// Test for regular expression tokens
+fn token_regex() {
+    // Without groups, the matched string is the result
+    assert_eq!(
+        run("r'(\\d+)(\\.\\d+)?' | r'[a-z]+'", "ab 12.5 x 3"),
+        Ok(Some(value![["ab", ["12", ".5"], "x", ["3", void]]]))
+    );
+
+    // Named groups result in a dict
+    assert_eq!(
+        run("r'(?P<key>\\w+)=(\\w+)'", "a=1 bc=de"),
+        Ok(Some(value![[
+            ["key" => "a", "2" => "1"],
+            ["key" => "bc", "2" => "de"]
+        ]]))
+    );
+
+    // Matches are anchored, and may span over multiple lines
+    assert_eq!(run("r'a\\s+b'", "xa\n  b a\nc"), Ok(Some(value!("a\n  b"))));
+    assert_eq!(
+        run("r'[a\\n]*' $0.len()", &"a\n".repeat(10000)),
+        Ok(Some(value!(20000)))
+    );
+
+    // Only quotes are escaped
+    assert_eq!(run("r'it\\'s'", "it's"), Ok(Some(value!("it's"))));
+
+    assert_eq!(
+        run("r'(x'", ""),
+        Err("Line 1, column 1: Invalid regular expression: unclosed group".to_string())
+    );
+}
+
 #[test]
 // Testing examples provided in the examples folder
 fn examples() {
@@ -663,5 +778,684 @@ fn push_next() {
 fn loops() {
     testcase("tests/test_loop.tok");
     testcase("tests/test_for.tok");
+    testcase("tests/test_for_in.tok");
     testcase("tests/err_break_continue.tok");
 }
+
+#[test]
+// Test for error recovery
+fn recover() {
+    testcase("tests/test_recover.tok");
+
+    // Recovered errors are collected by the runtime
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str("recover((Int _ expect ',' _), ',') _")
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let mut reader = crate::Reader::new(Box::new(std::io::Cursor::new("1, 2 3, x, 4")));
+    let mut runtime = crate::vm::Runtime::new(&program, &mut reader);
+
+    let ret = program.run(&mut runtime).unwrap().unwrap();
+    assert_eq!(ret.borrow().object::<List>().unwrap().len(), 3);
+
+    assert_eq!(
+        runtime
+            .errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<String>>(),
+        vec![
+            "Line 1, column 6: Expecting ','",
+            "Line 1, column 13: Expecting ','"
+        ]
+    );
+
+    // Offsets of error nodes are absolute, also after several runs of main
+    let ret = run("recover((Int expect ','), ';')", "1;2;3;")
+        .unwrap()
+        .unwrap();
+    let ret = ret.borrow();
+
+    assert_eq!(
+        ret.object::<List>()
+            .unwrap()
+            .iter()
+            .map(|node| node.borrow().object::<Dict>().unwrap()["offset"].to_usize())
+            .collect::<Result<Vec<usize>, String>>(),
+        Ok(vec![1, 3, 5])
+    );
+}
+
+#[test]
+// Test for try-catch
+fn try_catch() {
+    testcase("tests/test_try.tok");
+
+    // The error is a value with message and position, and the input is read again
+    assert_eq!(
+        run(
+            "try { Int _ expect Word } catch err { Int _ Int (err.message, err.offset, err.row, err.col) }",
+            "42 23"
+        ),
+        Ok(Some(value!([
+            42,
+            23,
+            [
+                "Expecting <builtin Word>",
+                (3 as usize),
+                (1 as usize),
+                (4 as usize)
+            ]
+        ])))
+    );
+
+    // Reading continues from where the try-block started, errors in the catch-part are raised
+    assert_eq!(
+        run(
+            "try { Int \"x\" error(\"oops\") } catch { Int Word }",
+            "42xy"
+        ),
+        Ok(Some(value!([42, "xy"])))
+    );
+
+    assert_eq!(
+        run(
+            "try { try error(\"inner\") catch err error(\"outer \" + err.message) } catch err err.message",
+            ""
+        ),
+        Ok(Some(value!("outer inner")))
+    );
+
+    assert_eq!(
+        run("try error(\"uncaught\") catch err error(err.message)", ""),
+        Err("Line 1, column 1: uncaught".to_string())
+    );
+
+    // Without errors, the try-block's result is returned
+    assert_eq!(run("try 42 catch err 0", ""), Ok(Some(value!(42))));
+
+    // A try which may not consume is nullable, so left-recursion behind it is detected
+    assert_eq!(
+        run(
+            "A : @{ try expect 'x' catch err void  A 'b' | 'c' }\nA",
+            "cb"
+        ),
+        Ok(Some(value!(["c", "b"])))
+    );
+
+    // The catch variable is a plain variable, otherwise a block is required
+    assert_eq!(
+        run("try error(\"oops\") catch print(\"handled\")", ""),
+        Err("Line 1, column 25: 'catch': Expecting variable and statement, or a { ... } block without variable".to_string())
+    );
+    assert_eq!(
+        run("try error(\"oops\") catch { \"handled\" }", ""),
+        Ok(Some(value!("handled")))
+    );
+}
+
+#[test]
+// Test for memoization policies and annotations
+fn memoization() {
+    use crate::vm::{Memoization, Profile, Reject, Runtime};
+
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str(
+            "
+            Expr : @{ Expr '+' Term  $1 + $3 | Term }
+            Term : @{ Term '*' Atom  $1 * $3 | Atom }
+            Atom : @{ Int | '(' Expr ')' }
+            Expr
+            ",
+        )
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    // Left recursion works with any policy, even with a memo table bounded to one entry
+    for memoization in [
+        Memoization::Full,
+        Memoization::Bounded(1),
+        Memoization::Linear,
+    ] {
+        let mut reader =
+            crate::Reader::new(Box::new(std::io::Cursor::new("1+2*3+(4+5)*2 3*(1+1)")));
+        let mut runtime = Runtime::new(&program, &mut reader);
+        runtime.memoization = memoization;
+
+        assert_eq!(
+            program.run(&mut runtime),
+            Ok(Some(value!([25, 6]))),
+            "{:?}",
+            memoization
+        );
+
+        match memoization {
+            Memoization::Bounded(limit) => assert!(runtime.memo.len() <= limit),
+            Memoization::Linear => assert!(runtime.memo.is_empty()),
+            _ => {}
+        }
+    }
+
+    // Memoizing a key again doesn't evict other entries too early
+    let mut reader = crate::Reader::new(Box::new(std::io::Cursor::new("")));
+    let mut runtime = Runtime::new(&program, &mut reader);
+    runtime.memoization = Memoization::Bounded(2);
+
+    for offset in [0, 0, 1] {
+        runtime.memoize((offset, None, 0), runtime.reader.tell(), Err(Reject::Next));
+    }
+
+    assert_eq!(runtime.memo.len(), 2);
+    assert_eq!(runtime.memo_order.len(), 2);
+
+    // Parselets only called at the start of a memoized parselet are not memoized,
+    // unless annotated otherwise
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str(
+            "
+            Key : @{ Word }
+            Pair : @{ Key '=' Int }
+            Value : @[memo] { Int }
+            Item : @{ Pair ';' | Pair | Value }
+            Other : @[nomemo] { Item }
+            Other
+            ",
+        )
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let mut reader = crate::Reader::new(Box::new(std::io::Cursor::new("a=1;b=2 3")));
+    let mut runtime = Runtime::new(&program, &mut reader);
+    runtime.profile = Some(Profile::new());
+
+    assert_eq!(
+        program.run(&mut runtime),
+        Ok(Some(value!([["a", 1], ["b", 2], 3])))
+    );
+
+    let profile = runtime.profile.unwrap();
+    let entries = profile.entries();
+    let entry = |name| *entries.iter().find(|entry| entry.name == name).unwrap();
+
+    assert_eq!(entry("Key").memo_misses, 0); // only called at the start of Pair
+    assert!(entry("Pair").memo_hits > 0); // called twice at the start of Item
+    assert!(entry("Value").memo_misses > 0); // annotated to be memoized
+    assert_eq!(entry("Other").memo_misses, 0); // annotated not to be memoized
+
+    // Unknown annotations are reported
+    assert_eq!(
+        run("X : @[foo] { 'x' }", ""),
+        Err("Line 1, column 7: Unknown parselet annotation 'foo'".to_string())
+    );
+}
+
+#[test]
+// Test for cutting input
+fn cut() {
+    testcase("tests/test_cut.tok");
+
+    // Failure after a cut is an error at the farthest position
+    assert_eq!(
+        run("Pair : @{ Word cut ':' Int  ($1, $3) }\nPair", "abc:1 d:x"),
+        Err("Line 1, column 9: Expecting Int".to_string())
+    );
+
+    // Alternatives before a cut are not tried anymore
+    assert_eq!(
+        run("Pair : @{ Word ':' Int  ($1, $3) | Word }\nPair", "abc:1 d"),
+        Ok(Some(value!([["abc", 1], "d"])))
+    );
+
+    assert_eq!(
+        run(
+            "Pair : @{ Word cut ':' Int  ($1, $3) | Word }\nPair",
+            "abc:1 d"
+        ),
+        Err("Line 1, column 8: Expecting ':'".to_string())
+    );
+
+    // Left recursion works with cuts
+    assert_eq!(
+        run("X : @{ X '+' cut Int | Int }\nX", "1+2+3"),
+        Ok(Some(value!([[1, 2], 3])))
+    );
+
+    // Captures before a cut are kept
+    assert_eq!(
+        run("Pair : @{ Word cut _ Int  ($1, $3) }\nPair", "abc 123"),
+        Ok(Some(value!(["abc", 123])))
+    );
+
+    // Input before a cut is removed from the reader, while offsets keep counting
+    let mut reader = crate::Reader::new(Box::new(std::io::Cursor::new("abc 123")));
+    reader.advance_to(4);
+    let start = reader.tell();
+
+    reader.cut();
+    assert_eq!(reader.base(), 4);
+    assert_eq!(reader.buffered(), b"123");
+
+    reader.advance_to(6);
+    assert_eq!(reader.tell().offset, 6);
+    assert_eq!(reader.get(&reader.capture_from(&start)), "12");
+}
+
+#[test]
+// Test for deep recursion
+fn recursion_depth() {
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str("Nest : @{ '(' Nest ')' | '(' ')' }\nNest  1")
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let nest = |depth| "(".repeat(depth) + &")".repeat(depth);
+
+    // Deep nesting ends the run with an error at the default limit
+    assert_eq!(
+        program
+            .run_from_string(nest(100_000))
+            .map_err(|error| error.message),
+        Err(format!(
+            "Maximum recursion depth of {} exceeded",
+            crate::vm::MAX_DEPTH
+        ))
+    );
+
+    // The limit is configurable
+    let input = nest(2_000);
+
+    for (max_depth, result) in [
+        (
+            Some(1_000),
+            Err("Maximum recursion depth of 1000 exceeded".to_string()),
+        ),
+        (None, Ok(Some(value!(1)))),
+    ] {
+        let mut reader = crate::Reader::new(Box::new(std::io::Cursor::new(input.clone())));
+        let mut runtime = crate::vm::Runtime::new(&program, &mut reader);
+        runtime.max_depth = max_depth;
+
+        // Without a limit, the native stack grows beyond the test thread's stack size
+        assert_eq!(
+            program.run(&mut runtime).map_err(|error| error.message),
+            result
+        );
+    }
+}
+
+#[test]
+// Test for execution limits
+fn limits() {
+    use crate::vm::{Cancel, Limit, Runtime};
+    use std::time::{Duration, Instant};
+
+    // Runs a program with limits applied to its runtime, returning the exceeded limit
+    fn run_limited(src: &str, input: &str, apply: impl FnOnce(&mut Runtime)) -> Option<Limit> {
+        let mut compiler = crate::Compiler::new(true);
+        compiler.compile_from_str(src).unwrap();
+        let program = compiler.finalize().unwrap();
+
+        let mut reader = crate::Reader::new(Box::new(std::io::Cursor::new(input.to_string())));
+        let mut runtime = Runtime::new(&program, &mut reader);
+        apply(&mut runtime);
+
+        program.run(&mut runtime).unwrap_err().limit
+    }
+
+    let endless = "begin loop { }";
+
+    assert_eq!(
+        run_limited(endless, "", |runtime| runtime.limits.steps = Some(1000)),
+        Some(Limit::Steps(1000))
+    );
+
+    assert_eq!(
+        run_limited(endless, "", |runtime| runtime.limits.deadline =
+            Some(Instant::now() + Duration::from_millis(50))),
+        Some(Limit::Timeout)
+    );
+
+    // A handle cancels the run from another thread
+    let cancel = Cancel::new();
+    let handle = cancel.clone();
+
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.cancel();
+    });
+
+    assert_eq!(
+        run_limited(endless, "", |runtime| runtime.limits.cancel = Some(cancel)),
+        Some(Limit::Cancelled)
+    );
+
+    thread.join().unwrap();
+
+    // Stack and memo size are capped
+    let nest = "Nest : @{ '(' Nest ')' | '(' ')' }\nNest";
+
+    assert_eq!(
+        run_limited(nest, &("(".repeat(100) + &")".repeat(100)), |runtime| {
+            runtime.limits.stack = Some(50)
+        }),
+        Some(Limit::Stack(50))
+    );
+
+    assert_eq!(
+        run_limited(nest, &("(".repeat(100) + &")".repeat(100)), |runtime| {
+            runtime.limits.memo = Some(10)
+        }),
+        Some(Limit::Memo(10))
+    );
+
+    // Limit errors are not caught by recover()
+    assert_eq!(
+        run_limited("recover({ loop { } }, ';')", "x", |runtime| {
+            runtime.limits.steps = Some(1000)
+        }),
+        Some(Limit::Steps(1000))
+    );
+
+    // Limit errors are not caught by try-catch
+    assert_eq!(
+        run_limited("try { loop { } } catch err 1", "", |runtime| {
+            runtime.limits.steps = Some(1000)
+        }),
+        Some(Limit::Steps(1000))
+    );
+
+    // Errors raised by the program itself are not limit errors
+    assert_eq!(run_limited("error(\"Hello\")", "", |_| {}), None);
+}
+
+#[test]
+// Test for compiled program serialization
+fn bytecode() {
+    use crate::vm::{Op, Program};
+
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str(
+            r#"
+            Key : @{ [a-zA-Z_]+  $1.upper() | r'"[^"]*"' }
+            Value : @{
+                'true'i  true
+                'null'i  null
+                Float
+                Int
+                [\p{Greek}]+
+                Alphanumerics
+                '[' _ (Value _ ','? _)* ']'  $3
+            }
+            Pair : @{ Key _ ':' _ Value  (key => $1, value => $5) }
+            Pair _  ($1, 123456789012345678901234567890 + 1)
+            "#,
+        )
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let bytecode = program.to_bytecode().unwrap();
+    assert!(Program::is_bytecode(&bytecode));
+
+    // Loaded programs serialize into the same bytecode, and run as the compiled program
+    let loaded = Program::from_bytecode(&bytecode).unwrap();
+    assert_eq!(loaded.to_bytecode().unwrap(), bytecode);
+
+    let input = "a: TRUE \"b c\": [1, 2.5 αβγ x1] d: Null";
+
+    assert_eq!(loaded.run_from_str(input), program.run_from_str(input));
+    assert_eq!(
+        loaded.run_from_str(input).unwrap().unwrap().repr(),
+        r#"(((key => "A", value => true), 123456789012345678901234567891), ((key => "\"b c\"", value => (1, 2.5, "αβγ", "x1")), 123456789012345678901234567891), ((key => "D", value => null), 123456789012345678901234567891))"#
+    );
+
+    // Invalid bytecode is rejected
+    assert_eq!(
+        Program::from_bytecode(b"Key : @{ Int }")
+            .unwrap_err()
+            .message,
+        "Not a Tokay bytecode file"
+    );
+    assert_eq!(
+        Program::from_bytecode(&bytecode[..bytecode.len() / 2])
+            .unwrap_err()
+            .message,
+        "Unexpected end of bytecode"
+    );
+
+    // Addresses out of range are rejected
+    let parselet = Parselet::new(
+        Some("main".to_string()),
+        None,
+        5,
+        Vec::new(),
+        0,
+        Vec::new(),
+        Vec::new(),
+        vec![Op::CallStatic(99)],
+    );
+    let bytecode = Program::new(vec![parselet.into()]).to_bytecode().unwrap();
+
+    assert_eq!(
+        Program::from_bytecode(&bytecode).unwrap_err().message,
+        "Invalid address in parselet 'main'"
+    );
+
+    // Deeply nested values are rejected
+    let mut bytecode = Program::new(Vec::new()).to_bytecode().unwrap();
+    bytecode.pop(); // number of statics
+    bytecode.push(1);
+    for _ in 0..100000 {
+        bytecode.extend([7, 1]);
+    }
+    bytecode.push(0);
+
+    assert_eq!(
+        Program::from_bytecode(&bytecode).unwrap_err().message,
+        "Values nested too deeply in bytecode"
+    );
+}
+
+#[test]
+// Test for importing modules
+fn import() {
+    testcase("tests/test_import.tok");
+
+    // Modules can be imported under different namespaces
+    assert_eq!(
+        run(
+            "import \"tests/lib/chars.tok\"\nimport \"tests/lib/chars\" as c\nchars::Alpha c::Alnum  $0",
+            "a1 b2"
+        ),
+        Ok(Some(value!(["a1", "b2"])))
+    );
+
+    // Modules are found in the search path
+    let mut compiler = crate::Compiler::new(true);
+    compiler.search_path.push("tests/lib".into());
+    assert!(compiler
+        .compile_from_str("import \"chars\"\nchars::Alpha")
+        .is_ok());
+
+    // Errors
+    assert_eq!(
+        run("import \"tests/lib/missing.tok\"", ""),
+        Err("Line 1, column 1: Module 'tests/lib/missing.tok' not found".to_string())
+    );
+
+    assert!(run("import \"tests/lib/cycle_a.tok\"", "")
+        .unwrap_err()
+        .contains("Import cycle detected"));
+
+    assert_eq!(
+        run("import \"tests/lib/my-chars.tok\"", ""),
+        Err(
+            "Line 1, column 1: Invalid namespace 'my-chars'; Use 'import \"tests/lib/my-chars.tok\" as name' instead"
+                .to_string()
+        )
+    );
+
+    assert_eq!(
+        run("import \"tests/lib/chars.tok\"\nchars::Alpha = 1", ""),
+        Err("Line 2, column 1: Cannot assign to constant 'chars::Alpha'".to_string())
+    );
+
+    assert_eq!(
+        run("import \"tests/lib/chars.tok\"\nchars::Digit", ""),
+        Err("Line 2, column 1: Call to unresolved symbol 'chars::Digit'".to_string())
+    );
+
+    // Modules have no global variables, so they can't alias the importer's globals
+    assert_eq!(
+        run("a = 100\nimport \"tests/lib/counter\"\ncounter::Inc", "xxx"),
+        Err("tests/lib/counter.tok:3:1: Cannot define global variable 'count' inside of a module; Use a constant instead".to_string())
+    );
+
+    assert_eq!(
+        run("a : { import \"tests/lib/chars.tok\" }", ""),
+        Err("Line 1, column 7: 'import' is only allowed at the top-level".to_string())
+    );
+}
+
+#[test]
+// Test for generic parselets
+fn generics() {
+    testcase("tests/test_generics.tok");
+
+    // Every instance is analyzed on its own, here for left-recursion
+    assert_eq!(
+        run(
+            "Chain : @<X> { Chain<X> '.' X | X }\nChain<Int> _ Chain<Word>",
+            "1.2.3 a.b"
+        ),
+        Ok(Some(value!([[[1, 2], 3], ["a", "b"]])))
+    );
+
+    // Instances can be called with arguments
+    assert_eq!(
+        run(
+            "Tagged : @<X> tag { X  (tag, $1) }\nTagged<Int>(\"int\")",
+            "42"
+        ),
+        Ok(Some(value!(["int", 42])))
+    );
+
+    // Generic parselets of modules are instantiated within their module
+    assert_eq!(
+        run(
+            "import \"tests/lib/lexer.tok\"\nlexer::Separated<';'>",
+            "1; 2"
+        ),
+        Ok(Some(value!([1, 2])))
+    );
+
+    // Errors
+    assert_eq!(
+        run("List : @<Item> { Item+ }\nList", ""),
+        Err(
+            "Line 2, column 1: Generic parselet 'List' requires generic arguments, e.g. 'List<...>'"
+                .to_string()
+        )
+    );
+
+    assert_eq!(
+        run("List : @<Item, Sep> { Item (Sep Item)* }\nList<Int>", ""),
+        Err("Line 2, column 1: Missing generic argument 'Sep' for 'List'".to_string())
+    );
+
+    assert_eq!(
+        run("List : @<Item> { Item+ }\nList<Int, ','>", ""),
+        Err(
+            "Line 2, column 1: Too many generic arguments for 'List', expecting 1 at most"
+                .to_string()
+        )
+    );
+
+    assert_eq!(
+        run("List : @<Item> { Item+ }\nList<1>", ""),
+        Err(
+            "Line 2, column 1: Generic 'Item' of 'List' must be consumable, but 1 is not"
+                .to_string()
+        )
+    );
+
+    assert_eq!(
+        run("Pair<Int>", ""),
+        Err("Line 1, column 1: Use of undefined generic parselet 'Pair'".to_string())
+    );
+
+    assert_eq!(
+        run(
+            "Nest : @<X> { \"[\" Nest<Nest<X>> \"]\" | X }\nNest<Int>",
+            "1"
+        ),
+        Err(
+            "Line 1, column 19: Generic instantiation too deep, 'Nest' exceeds 8 nested instances"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+// Test for catch-all arguments *args and **nargs
+fn catchall_arguments() {
+    let f = "f : @a, b=2, *args, **nargs { (a, b, args, nargs) }\n";
+
+    assert_eq!(
+        run(&format!("{}f(1)", f), ""),
+        Ok(Some(value!([1, 2, (List::new()), (Dict::new())])))
+    );
+
+    assert_eq!(
+        run(&format!("{}f(1, 3, 4, 5)", f), ""),
+        Ok(Some(value!([1, 3, [4, 5], (Dict::new())])))
+    );
+
+    assert_eq!(
+        run(&format!("{}f(1, b=3, c=4)", f), ""),
+        Ok(Some(value!([1, 3, (List::new()), ["c" => 4]])))
+    );
+
+    assert_eq!(
+        run(&format!("{}f(1, 2, 3, c=4)", f), ""),
+        Ok(Some(value!([1, 2, [3], ["c" => 4]])))
+    );
+
+    assert_eq!(
+        run("f : @*args { args }\nf(1, \"x\")", ""),
+        Ok(Some(value!([1, "x"])))
+    );
+
+    // Without catch-all arguments, further arguments are rejected
+    assert_eq!(
+        run("f : @a { a }\nf(1, 2)", ""),
+        Err("Line 2, column 1: f() takes exactly one argument (2 given); Use '*args' to accept further arguments".to_string())
+    );
+
+    assert_eq!(
+        run("f : @*args { args }\nf(x=1)", ""),
+        Err(
+            "Line 2, column 1: f() doesn't accept named argument 'x'; Use '**nargs' to accept it"
+                .to_string()
+        )
+    );
+
+    // Catch-all arguments follow any other arguments, in order
+    assert_eq!(
+        run("f : @*args, a { a }", ""),
+        Err(
+            "Line 1, column 13: Argument 'a' must be specified before any catch-all argument"
+                .to_string()
+        )
+    );
+
+    assert_eq!(
+        run("f : @**nargs, *args { args }", ""),
+        Err("Line 1, column 15: Catch-all argument '*args' not allowed here; Use '*args' and '**nargs' only once, in this order".to_string())
+    );
+}
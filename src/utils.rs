@@ -29,7 +29,20 @@ pub fn run(src: &str, input: &str) -> Result<Option<RefValue>, String> {
 }
 
 /// Checks if an identifier defines a Tokay consumable.
+/// For identifiers qualified by a namespace, the name after the namespace counts.
 pub(crate) fn identifier_is_consumable(ident: &str) -> bool {
+    let ident = ident.rsplit("::").next().unwrap();
     let ch = ident.chars().next().unwrap();
     ch.is_uppercase() || ch == '_'
 }
+
+/// Converts the digits of an escape sequence in radix into a character.
+/// Up to two digits are a byte, longer codes are a Unicode code point.
+pub(crate) fn code_to_char(digits: &str, radix: u32) -> char {
+    if digits.len() <= 2 {
+        u8::from_str_radix(digits, radix).unwrap_or_default() as char
+    } else {
+        std::char::from_u32(u32::from_str_radix(digits, radix).unwrap_or_default())
+            .unwrap_or_default()
+    }
+}
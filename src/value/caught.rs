@@ -0,0 +1,84 @@
+//! Caught error object
+use super::{BoxedObject, Object, RefValue};
+use crate::value;
+use crate::Error;
+
+/// Error object type, as caught by try-catch
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Caught {
+    message: String, // Error message
+    offset: usize,   // Absolute input offset where the error occurred
+    row: usize,      // Input row where the error occurred
+    col: usize,      // Input column where the error occurred
+}
+
+impl Caught {
+    /// Creates a caught error from an error, with its absolute input offset.
+    pub fn new(error: Error, offset: usize) -> Self {
+        let position = error.offset.unwrap();
+
+        Self {
+            message: error.message,
+            offset,
+            row: position.row as usize,
+            col: position.col as usize,
+        }
+    }
+}
+
+impl Object for Caught {
+    fn name(&self) -> &'static str {
+        "error"
+    }
+
+    fn repr(&self) -> String {
+        format!(
+            "<error at {}:{} {}>",
+            self.row,
+            self.col,
+            value!(self.message.clone()).repr()
+        )
+    }
+
+    fn to_string(&self) -> String {
+        self.message.clone()
+    }
+
+    fn attr(&self, name: &str) -> Option<RefValue> {
+        Some(match name {
+            "message" => value!(self.message.clone()),
+            "offset" => value!(self.offset),
+            "row" => value!(self.row),
+            "col" => value!(self.col),
+            _ => return None,
+        })
+    }
+}
+
+impl From<Caught> for RefValue {
+    fn from(value: Caught) -> Self {
+        RefValue::from(Box::new(value) as BoxedObject)
+    }
+}
+
+#[test]
+fn test_caught() {
+    assert_eq!(
+        crate::run(
+            "try error(\"oops\") catch err (repr(err), err.message, err.row, err.col, str(err))",
+            ""
+        ),
+        Ok(Some(crate::value!([
+            "<error at 1:1 \"oops\">",
+            "oops",
+            (1 as usize),
+            (1 as usize),
+            "oops"
+        ])))
+    );
+
+    assert_eq!(
+        crate::run("try error(\"oops\") catch err err.cause", ""),
+        Err("Line 1, column 32: Method 'error_cause' not found".into())
+    );
+}
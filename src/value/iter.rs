@@ -0,0 +1,184 @@
+//! Iterator object
+use super::{BoxedObject, Dict, List, Object, RefValue, Str};
+use crate::value;
+use num::{Signed, Zero};
+use num_bigint::BigInt;
+use tokay_macros::{tokay_function, tokay_method};
+extern crate self as tokay;
+
+/// Source of an iterator
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+enum Source {
+    Object(RefValue), // Items of a list, keys of a dict or characters of a str
+    Range(BigInt, BigInt, BigInt), // Integers from start to stop by step
+}
+
+/// Iterator object type, as used by for-in loops
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Iter {
+    source: Source,
+    index: usize,  // Number of items yielded so far
+    offset: usize, // Byte offset into a str
+}
+
+impl Object for Iter {
+    fn name(&self) -> &'static str {
+        "iter"
+    }
+
+    fn repr(&self) -> String {
+        match &self.source {
+            Source::Object(object) => format!("<iter {}>", object.borrow().repr()),
+            Source::Range(start, stop, step) => format!("range({}, {}, {})", start, stop, step),
+        }
+    }
+}
+
+impl Iter {
+    /// Creates an iterator over a list, dict or str, or a copy of an existing iterator.
+    pub fn new(value: RefValue) -> Result<Self, String> {
+        {
+            let object = value.borrow();
+
+            if let Some(iter) = object.object::<Iter>() {
+                return Ok(iter.clone());
+            }
+
+            if !(object.is("list") || object.is("dict") || object.is("str")) {
+                return Err(format!("'{}' is not iterable", object.name()));
+            }
+        }
+
+        Ok(Self {
+            source: Source::Object(value),
+            index: 0,
+            offset: 0,
+        })
+    }
+
+    /// Creates an iterator over a range of integers, excluding stop.
+    pub fn range(start: BigInt, stop: BigInt, step: BigInt) -> Result<Self, String> {
+        if step.is_zero() {
+            return Err("range() step must not be 0".to_string());
+        }
+
+        Ok(Self {
+            source: Source::Range(start, stop, step),
+            index: 0,
+            offset: 0,
+        })
+    }
+
+    /// Advances the iterator, returning the next key and item.
+    fn advance(&mut self) -> Option<(RefValue, RefValue)> {
+        let ret = match &self.source {
+            Source::Object(object) => {
+                let object = object.borrow();
+
+                if let Some(list) = object.object::<List>() {
+                    let item = list.get(self.index)?;
+                    (value!(self.index), item.clone())
+                } else if let Some(dict) = object.object::<Dict>() {
+                    let (key, item) = dict.get_index(self.index)?;
+                    (value!(key.clone()), item.clone())
+                } else if let Some(string) = object.object::<Str>() {
+                    let ch = string.as_str().get(self.offset..)?.chars().next()?;
+                    self.offset += ch.len_utf8();
+                    (value!(self.index), value!(ch.to_string()))
+                } else {
+                    return None;
+                }
+            }
+            Source::Range(start, stop, step) => {
+                let next = start + step * BigInt::from(self.index);
+
+                if (step.is_positive() && next >= *stop) || (step.is_negative() && next <= *stop) {
+                    return None;
+                }
+
+                (value!(self.index), RefValue::from(next))
+            }
+        };
+
+        self.index += 1;
+        Some(ret)
+    }
+
+    /// Returns the next item; Iterating a dict returns its keys.
+    pub fn next_item(&mut self) -> Option<RefValue> {
+        let is_dict = matches!(&self.source, Source::Object(object) if object.is("dict"));
+        let (key, item) = self.advance()?;
+
+        Some(if is_dict { key } else { item })
+    }
+
+    /// Returns the next key and item; Keys of a list, str or range are the item's index.
+    pub fn next_pair(&mut self) -> Option<(RefValue, RefValue)> {
+        self.advance()
+    }
+
+    tokay_method!("iter(value)", Ok(RefValue::from(Iter::new(value)?)));
+
+    tokay_method!("iter_next(iter)", {
+        let mut iter = iter.borrow_mut();
+
+        if let Some(iter) = iter.object_mut::<Iter>() {
+            Ok(iter.next_item().unwrap_or_else(|| value!(void)))
+        } else {
+            Err(format!("{} only accepts 'iter' as parameter", __function).into())
+        }
+    });
+}
+
+tokay_function!("range(start, stop=void, step=1)", {
+    // With only one argument, the range starts at 0 and stops before it
+    let (start, stop) = if stop.is_void() {
+        (BigInt::zero(), start.to_bigint()?)
+    } else {
+        (start.to_bigint()?, stop.to_bigint()?)
+    };
+
+    RefValue::from(Iter::range(start, stop, step.to_bigint()?)?).into()
+});
+
+impl From<Iter> for RefValue {
+    fn from(value: Iter) -> Self {
+        RefValue::from(Box::new(value) as BoxedObject)
+    }
+}
+
+#[test]
+fn test_iter() {
+    assert_eq!(
+        crate::run(
+            "i = iter((1, 2)); i.next() i.next() repr(i.next()) iter(\"äb\").next()",
+            ""
+        ),
+        Ok(Some(crate::value!([1, 2, "void", "ä"])))
+    );
+
+    assert_eq!(
+        crate::run("iter(42)", ""),
+        Err("Line 1, column 1: 'int' is not iterable".into())
+    );
+}
+
+#[test]
+fn test_range() {
+    assert_eq!(
+        crate::run(
+            "repr(range(3)) repr(range(1, 10, 3)) repr(range(3, 0, -1))",
+            ""
+        ),
+        Ok(Some(crate::value!([
+            "range(0, 3, 1)",
+            "range(1, 10, 3)",
+            "range(3, 0, -1)"
+        ])))
+    );
+
+    assert_eq!(
+        crate::run("range(1, 2, 0)", ""),
+        Err("Line 1, column 1: range() step must not be 0".into())
+    );
+}
@@ -1,5 +1,7 @@
 //! Tokay value and object representation
+mod caught;
 pub mod dict;
+pub mod iter;
 pub mod list;
 mod method;
 mod object;
@@ -10,7 +12,9 @@ pub mod token;
 pub mod value;
 
 pub use self::str::Str;
+pub use caught::Caught;
 pub use dict::Dict;
+pub use iter::Iter;
 pub use list::List;
 pub use method::Method;
 pub use object::{BoxedObject, Object};
@@ -1,4 +1,4 @@
-use super::Dict;
+use super::{Dict, RefValue};
 use crate::{Accept, Context, Reject};
 use num_bigint::BigInt;
 use std::any::Any;
@@ -203,6 +203,11 @@ pub trait Object:
         false
     }
 
+    /// Object attribute which isn't a method, e.g. a field
+    fn attr(&self, _name: &str) -> Option<RefValue> {
+        None
+    }
+
     /// Call object with a given context, argument and named argument set.
     fn call(
         &self,
@@ -5,7 +5,7 @@ use std::rc::Rc;
 
 use super::{BoxedObject, Dict, List, Object, RefValue};
 
-use crate::error::Error;
+use crate::error::{Error, Trace};
 use crate::vm::*;
 
 /** Parselet is the conceptual building block of a Tokay program.
@@ -26,11 +26,14 @@ pub struct Parselet {
     pub(crate) name: String, // Parselet's name from source (for debugging)
     pub(crate) consuming: Option<bool>, // Indicator for consuming & left-recursion
     pub(crate) severity: u8, // Capture push severity
-    signature: Vec<(String, Option<usize>)>, // Argument signature with default arguments
+    pub(crate) memo: bool,   // Memoize results of this parselet
+    pub(crate) signature: Vec<(String, Option<usize>)>, // Argument signature with default arguments
+    pub(crate) args_catchall: Option<usize>, // Local receiving further arguments (*args)
+    pub(crate) nargs_catchall: Option<usize>, // Local receiving further named arguments (**nargs)
     pub(crate) locals: usize, // Number of local variables present
-    begin: Vec<Op>,          // Begin-operations
-    end: Vec<Op>,            // End-operations
-    body: Vec<Op>,           // Operations
+    pub(crate) begin: Vec<Op>, // Begin-operations
+    pub(crate) end: Vec<Op>, // End-operations
+    pub(crate) body: Vec<Op>, // Operations
 }
 
 impl Parselet {
@@ -54,7 +57,10 @@ impl Parselet {
             name: name.unwrap_or(String::new()),
             consuming,
             severity,
+            memo: true,
             signature,
+            args_catchall: None,
+            nargs_catchall: None,
             locals,
             begin,
             end,
@@ -68,15 +74,83 @@ impl Parselet {
         ret
     }
 
+    /** Returns the addresses of static values referenced by the parselet.
+
+    Every reference is flagged whether it is a call which is only run at the parselet's start
+    offset and never repeated within the same run of the parselet. */
+    pub(crate) fn references(&self, statics: &[RefValue]) -> Vec<(usize, bool)> {
+        let mut refs = Vec::new();
+
+        // Parselets running their body repeatedly never have calls at their start only
+        let mut start = self.begin.is_empty()
+            && self.end.is_empty()
+            && !self
+                .body
+                .iter()
+                .any(|op| matches!(op, Op::Repeat | Op::LoadRepeat));
+
+        // Ranges of operations which may be executed repeatedly
+        let loops: Vec<std::ops::Range<usize>> = self
+            .body
+            .iter()
+            .enumerate()
+            .filter_map(|(i, op)| match op {
+                Op::Loop(size) => Some(i..i + size),
+                Op::Backward(n)
+                | Op::BackwardIfTrue(n)
+                | Op::BackwardIfFalse(n)
+                | Op::BackwardIfConsumed(n) => Some(i - n..i + 1),
+                _ => None,
+            })
+            .collect();
+
+        for op in self.begin.iter().chain(self.end.iter()) {
+            match op {
+                Op::CallStatic(addr) | Op::LoadStatic(addr) => refs.push((*addr, false)),
+                Op::CallStaticArg(addr_args) | Op::CallStaticArgNamed(addr_args) => {
+                    refs.push((addr_args.0, false))
+                }
+                _ => {}
+            }
+        }
+
+        for (i, op) in self.body.iter().enumerate() {
+            let addr = match op {
+                Op::CallStatic(addr) => *addr,
+                Op::CallStaticArg(addr_args) | Op::CallStaticArgNamed(addr_args) => addr_args.0,
+                Op::LoadStatic(addr) => {
+                    refs.push((*addr, false));
+                    continue;
+                }
+                Op::CallOrCopy
+                | Op::Call
+                | Op::CallArg(_)
+                | Op::CallArgNamed(_)
+                | Op::Rust(_)
+                | Op::Try(_)
+                | Op::Recover => {
+                    start = false;
+                    continue;
+                }
+                _ => continue,
+            };
+
+            refs.push((addr, start && !loops.iter().any(|range| range.contains(&i))));
+
+            // Any consuming call moves the reader away from the start
+            if statics[addr].is_consuming() {
+                start = false;
+            }
+        }
+
+        refs
+    }
+
     fn _run(&self, context: &mut Context, main: bool) -> Result<Accept, Reject> {
-        // Initialize parselet execution loop
-        let mut first = self.begin.len() > 0;
+        // Initialize parselet execution loop; A resumed main skips the begin-block.
+        let mut first = self.begin.len() > 0 && !(main && context.runtime.resume);
         let mut results = List::new();
-        let mut state = if self.begin.len() == 0 {
-            None
-        } else {
-            Some(true)
-        };
+        let mut state = if !first { None } else { Some(true) };
 
         // Debugging
         let mut debug = context.runtime.debug;
@@ -100,8 +174,19 @@ impl Parselet {
                 None => &self.body,
             };
 
+            if main {
+                context.runtime.reader.clear_starved();
+            }
+
             let mut result = Op::execute(ops, context, debug);
 
+            // An iteration of main which ran out of incrementally fed input is suspended,
+            // and repeated from its start when further input is available, also before a cut.
+            if main && state.is_none() && context.runtime.reader.is_starved() {
+                context.runtime.reader.rewind(context.reader_start.clone());
+                break Some(Ok(Accept::Next));
+            }
+
             // Either take $0 if it was set to a value, or
             // use any last remaining value as result,
             // if available
@@ -132,9 +217,9 @@ impl Parselet {
                     Ok(Accept::Return(value)) => Ok(Accept::Repeat(value)),
 
                     Ok(Accept::Push(capture)) => Ok(Accept::Repeat(match capture {
-                        Capture::Range(range, ..) => {
-                            Some(RefValue::from(context.runtime.reader.get(&range)))
-                        }
+                        Capture::Range(range, ..) => Some(RefValue::from(
+                            context.runtime.reader.get(&range).into_owned(),
+                        )),
                         Capture::Value(value, ..) => Some(value),
                         _ => None,
                     })),
@@ -167,7 +252,11 @@ impl Parselet {
 
                         Accept::Repeat(value) => {
                             if let Some(value) = value {
-                                results.push(value);
+                                // Deliver main results immediately, when requested
+                                match &mut context.runtime.results {
+                                    Some(sink) if main => sink.push(value),
+                                    _ => results.push(value),
+                                }
                             }
                         }
 
@@ -188,24 +277,20 @@ impl Parselet {
                     if main {
                         // In case no input was consumed in main loop, skip character
                         if state.is_none()
-                            && context
-                                .runtime
-                                .reader
-                                .capture_from(&context.reader_start)
-                                .len()
-                                == 0
+                            && context.runtime.reader.tell().offset == context.reader_start.offset
                         {
                             context.runtime.reader.next();
                         }
 
                         // Update absolute start offset
-                        context.runtime.start = context.runtime.reader.tell().offset;
+                        context.runtime.start += context.runtime.reader.tell().offset;
 
                         // Clear input buffer
                         context.runtime.reader.commit();
 
-                        // Clear memo table
-                        context.runtime.memo.clear();
+                        // Clear memo table and failure
+                        context.runtime.clear_memo();
+                        context.runtime.failure = None;
                     }
                 }
 
@@ -220,10 +305,17 @@ impl Parselet {
                         }
                         Reject::Error(mut err) => {
                             // Patch source position on error, when no position already set
-                            if let Some(source_offset) = context.source_offset {
-                                err.patch_offset(source_offset);
+                            if let Some(source_offset) = &context.source_offset {
+                                err.patch_offset(source_offset.clone());
                             }
 
+                            // Collect backtrace entry while the error bubbles up
+                            err.trace.push(Trace {
+                                parselet: self.name.clone(),
+                                source: context.source_offset.clone(),
+                                input: context.reader_start.clone(),
+                            });
+
                             break Some(Err(Reject::Error(err)));
                         }
                         Reject::Main if !main => break Some(Err(Reject::Main)),
@@ -232,6 +324,9 @@ impl Parselet {
 
                     // Skip character and reset reader start
                     if main && state.is_none() {
+                        // Failures of a skipped position aren't expected anymore
+                        context.runtime.failure = None;
+
                         context.runtime.reader.next();
                         context.reader_start = context.runtime.reader.tell();
                     } else if results.len() > 0 && state.is_none() {
@@ -296,17 +391,50 @@ impl Parselet {
         // Check for a previously memoized result in memo table
         let id = self as *const Parselet as usize;
 
-        // When parselet is consuming, try to read previous result from cache.
-        if self.consuming.is_some() {
+        // Left-recursive parselets always depend on the memo table, others according to policy.
+        let memoized = match self.consuming {
+            Some(true) => true,
+            Some(false) => self.memo && runtime.memoization != Memoization::Linear,
+            None => false,
+        };
+
+        // When parselet is memoized, try to read previous result from cache.
+        if memoized {
             // Get unique parselet id from memory address
             let reader_start = runtime.reader.tell();
 
-            if let Some((reader_end, result)) = runtime.memo.get(&(reader_start.offset, id)) {
-                runtime.reader.reset(*reader_end);
+            if let Some((reader_end, result)) =
+                runtime
+                    .memo
+                    .get(&(reader_start.offset, reader_start.indent.clone(), id))
+            {
+                if let Some(profile) = &mut runtime.profile {
+                    profile.call(id, &self.name, Some(true));
+                }
+
+                runtime.reader.reset(reader_end.clone());
                 return result.clone();
             }
         }
 
+        if let Some(profile) = &mut runtime.profile {
+            profile.call(id, &self.name, if memoized { Some(false) } else { None });
+        }
+
+        // Further arguments are collected by the *args catch-all
+        let mut args = args;
+        let mut rest = List::new();
+
+        if !main && self.args_catchall.is_some() && args > self.signature.len() {
+            let start = runtime.stack.len() - (args - self.signature.len());
+
+            for capture in runtime.stack.drain(start..) {
+                rest.push(capture.get_value());
+            }
+
+            args = self.signature.len();
+        }
+
         // If not, start a new context.
         let mut context = Context::new(
             runtime,
@@ -318,20 +446,19 @@ impl Parselet {
         );
 
         if !main {
-            // Check for provided argument count bounds first
-            // todo: Not executed when *args-catchall is implemented
+            // Check for provided argument count bounds first, without *args catch-all
             if args > self.signature.len() {
                 return Err(match self.signature.len() {
                     0 => format!(
-                        "{}() doesn't accept any arguments ({} given)",
+                        "{}() doesn't accept any arguments ({} given); Use '*args' to accept them",
                         self.name, args
                     ),
                     1 => format!(
-                        "{}() takes exactly one argument ({} given)",
+                        "{}() takes exactly one argument ({} given); Use '*args' to accept further arguments",
                         self.name, args
                     ),
                     _ => format!(
-                        "{}() expected at most {} arguments ({} given)",
+                        "{}() expected at most {} arguments ({} given); Use '*args' to accept further arguments",
                         self.name,
                         self.signature.len(),
                         args
@@ -373,14 +500,30 @@ impl Parselet {
                 }
             }
 
-            // Check for remaining nargs
-            // todo: Not executed when **nargs-catchall is implemented
+            // Provide the catch-alls with remaining arguments
+            if let Some(addr) = self.args_catchall {
+                context.runtime.stack[context.stack_start + addr] =
+                    Capture::Value(RefValue::from(rest), None, 0);
+            }
+
+            if let Some(addr) = self.nargs_catchall {
+                context.runtime.stack[context.stack_start + addr] = Capture::Value(
+                    RefValue::from(nargs.take().unwrap_or_else(Dict::new)),
+                    None,
+                    0,
+                );
+            }
+
+            // Check for remaining nargs, without **nargs catch-all
             if let Some(mut nargs) = nargs {
                 if let Some((name, _)) = nargs.pop() {
                     return Err(match nargs.len() {
-                        0 => format!("{}() doesn't accept named argument '{}'", self.name, name),
+                        0 => format!(
+                            "{}() doesn't accept named argument '{}'; Use '**nargs' to accept it",
+                            self.name, name
+                        ),
                         n => format!(
-                            "{}() doesn't accept named arguments ({} given)",
+                            "{}() doesn't accept named arguments ({} given); Use '**nargs' to accept them",
                             self.name,
                             n + 1
                         ),
@@ -405,6 +548,30 @@ impl Parselet {
 
         //println!("remaining {:?}", nargs);
 
+        // Whitespace and helper parselets named "_..." are never expected, so their failures are
+        // recorded apart from the failure before, which is restored afterwards.
+        let silent = if !main && self.name.starts_with('_') {
+            Some(context.runtime.failure.take())
+        } else {
+            None
+        };
+
+        // Named parselets take part in failure reporting, so remember the failure state before.
+        let report = if !main && self.name.starts_with(char::is_uppercase) {
+            Some(match &context.runtime.failure {
+                Some(failure) if failure.offset.offset == context.reader_start.offset => {
+                    failure.expected.len()
+                }
+                _ => 0,
+            })
+        } else {
+            None
+        };
+
+        if let Some(profile) = &mut context.runtime.profile {
+            profile.enter(id);
+        }
+
         // Perform left-recursive execution
         let result = if let Some(true) = self.consuming {
             /*
@@ -417,13 +584,17 @@ impl Parselet {
 
             // Left-recursive parselets are called in a loop until no more input
             // is consumed.
-            let mut reader_end = context.reader_start;
+            let mut reader_end = context.reader_start.clone();
             let mut result = Err(Reject::Next);
 
             // Insert a fake memo entry to avoid endless recursion
             context.runtime.memo.insert(
-                (context.reader_start.offset, id),
-                (reader_end, result.clone()),
+                (
+                    context.reader_start.offset,
+                    context.reader_start.indent.clone(),
+                    id,
+                ),
+                (reader_end.clone(), result.clone()),
             );
 
             loop {
@@ -452,14 +623,27 @@ impl Parselet {
                 result = loop_result;
                 reader_end = loop_end;
 
+                // Input was cut, so there's no way to grow any further
+                if context.reader_start.offset < context.runtime.reader.base() {
+                    break;
+                }
+
+                if let Some(profile) = &mut context.runtime.profile {
+                    profile.grow(id);
+                }
+
                 // Save intermediate result in memo table
                 context.runtime.memo.insert(
-                    (context.reader_start.offset, id),
-                    (reader_end, result.clone()),
+                    (
+                        context.reader_start.offset,
+                        context.reader_start.indent.clone(),
+                        id,
+                    ),
+                    (reader_end.clone(), result.clone()),
                 );
 
                 // Reset reader & stack
-                context.runtime.reader.reset(context.reader_start);
+                context.runtime.reader.reset(context.reader_start.clone());
                 context.runtime.stack.truncate(context.stack_start);
                 context
                     .runtime
@@ -469,20 +653,76 @@ impl Parselet {
 
             context.runtime.reader.reset(reader_end);
 
+            // The final result is only kept when memoization allows it
+            let key = (
+                context.reader_start.offset,
+                context.reader_start.indent.clone(),
+                id,
+            );
+
+            if !self.memo || context.runtime.memoization == Memoization::Linear {
+                context.runtime.memo.remove(&key);
+            } else {
+                context.runtime.bound_memo(key);
+            }
+
             result
         } else {
             let result = self._run(&mut context, main);
 
-            if !main && self.consuming.is_some() {
-                context.runtime.memo.insert(
-                    (context.reader_start.offset, id),
-                    (context.runtime.reader.tell(), result.clone()),
+            // Results from before a cut are never needed again, and errors carrying a backtrace
+            // are not memoized, as deep recursion would clone ever-growing traces.
+            if !main
+                && memoized
+                && context.reader_start.offset >= context.runtime.reader.base()
+                && !matches!(result, Err(Reject::Error(_)))
+            {
+                context.runtime.memoize(
+                    (
+                        context.reader_start.offset,
+                        context.reader_start.indent.clone(),
+                        id,
+                    ),
+                    context.runtime.reader.tell(),
+                    result.clone(),
                 );
             }
 
             result
         };
 
+        if let Some(profile) = &mut context.runtime.profile {
+            profile.leave(if result.is_ok() {
+                context
+                    .runtime
+                    .reader
+                    .tell()
+                    .offset
+                    .saturating_sub(context.reader_start.offset)
+            } else {
+                0
+            });
+        }
+
+        /*
+            A named parselet rejected at its start is reported as expected itself, instead of the
+            tokens it tried.
+        */
+        if let (Some(expected_len), Err(Reject::Next)) = (report, &result) {
+            let start = context.reader_start.clone();
+
+            match &mut context.runtime.failure {
+                Some(failure) if failure.offset.offset > start.offset => {}
+                Some(failure) if failure.offset.offset == start.offset => {
+                    failure.expected.truncate(expected_len);
+                    context.runtime.reject_at(&start, || self.name.clone());
+                }
+                _ => context.runtime.reject_at(&start, || self.name.clone()),
+            }
+        } else if let Some(failure) = silent {
+            context.runtime.failure = failure;
+        }
+
         /*
         // Dump AST when parselet returns an AST for debugging purposes.
         // fixme: Disabled for now, can be enabled on demand.
@@ -515,6 +755,12 @@ impl From<Parselet> for RefValue {
     }
 }
 
+// Remaining native stack size which requires to grow the stack before calling a parselet
+const STACK_RED_ZONE: usize = 128 * 1024;
+
+// Size of every further native stack segment allocated for deep recursion
+const STACK_GROWTH: usize = 4 * 1024 * 1024;
+
 #[derive(Clone, Debug)]
 pub struct ParseletRef(pub Rc<RefCell<Parselet>>);
 
@@ -547,9 +793,19 @@ impl Object for ParseletRef {
         args: usize,
         nargs: Option<Dict>,
     ) -> Result<Accept, Reject> {
-        self.0
-            .borrow()
-            .run(context.runtime, args, nargs, false, context.depth + 1)
+        // Calls beyond the runtime's recursion depth limit fail with an error
+        if let Some(max_depth) = context.runtime.max_depth {
+            if context.depth >= max_depth {
+                return Error::from(Limit::Depth(max_depth)).into();
+            }
+        }
+
+        // Grow the native stack on demand, so that deep recursion doesn't overflow it
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+            self.0
+                .borrow()
+                .run(context.runtime, args, nargs, false, context.depth + 1)
+        })
     }
 }
 
@@ -608,11 +864,11 @@ fn test_parselet_call_error_reporting() {
         ("f()", "Line 2, column 1: f() expected argument 'x'"),
         (
             "f(1, 2)",
-            "Line 2, column 1: f() takes exactly one argument (2 given)",
+            "Line 2, column 1: f() takes exactly one argument (2 given); Use '*args' to accept further arguments",
         ),
         (
             "f(1, y=2)",
-            "Line 2, column 1: f() doesn't accept named argument 'y'",
+            "Line 2, column 1: f() doesn't accept named argument 'y'; Use '**nargs' to accept it",
         ),
     ] {
         let call = format!("f : @x {{ x * x }}\n{}", call);
@@ -626,7 +882,7 @@ fn test_parselet_call_error_reporting() {
         ("f()", "Line 2, column 1: f() expected argument 'a'"),
         (
             "f(1, 2, 3, 4)",
-            "Line 2, column 1: f() expected at most 3 arguments (4 given)",
+            "Line 2, column 1: f() expected at most 3 arguments (4 given); Use '*args' to accept further arguments",
         ),
         (
             "f(c=10, d=3, e=10)",
@@ -634,11 +890,11 @@ fn test_parselet_call_error_reporting() {
         ),
         (
             "f(1, c=10, d=3)",
-            "Line 2, column 1: f() doesn't accept named argument 'd'",
+            "Line 2, column 1: f() doesn't accept named argument 'd'; Use '**nargs' to accept it",
         ),
         (
             "f(1, c=10, d=3, e=7)",
-            "Line 2, column 1: f() doesn't accept named arguments (2 given)",
+            "Line 2, column 1: f() doesn't accept named arguments (2 given); Use '**nargs' to accept them",
         ),
     ] {
         let call = format!("f : @a, b=2, c {{ a b c }}\n{}", call);
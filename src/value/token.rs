@@ -1,66 +1,345 @@
 //! Token callables represented by Value::Token
-use super::{BoxedObject, Dict, Object, RefValue};
-use crate::reader::Reader;
+use super::{BoxedObject, Dict, List, Object, RefValue};
+use crate::error::Error;
+use crate::reader::{Offset, Reader};
+use crate::utils::code_to_char;
 use crate::vm::*;
 use charclass::{charclass, CharClass};
-use num_bigint::BigInt;
+use num_bigint::{BigInt, Sign};
 use num_parse::*;
+use regex_automata::{hybrid, meta, Anchored, Input, PatternID};
+use regex_syntax::hir::{Class, ClassUnicode, HirKind};
+use std::cell::RefCell;
 use tokay_macros::tokay_token;
 extern crate self as tokay;
 
+/// Looks up the class of a Unicode property, e.g. a script like `Greek`, a general category
+/// like `Lu`, or a property given by `Script=Latin`.
+pub fn unicode_property(name: &str) -> Option<ClassUnicode> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "_=".contains(ch))
+    {
+        return None;
+    }
+
+    match regex_syntax::parse(&format!("\\p{{{}}}", name))
+        .ok()?
+        .kind()
+    {
+        HirKind::Class(Class::Unicode(class)) => Some(class.clone()),
+        _ => None,
+    }
+}
+
+/// Converts a Unicode class, with its set operations, into a CharClass.
+pub fn class_to_ccl(class: &ClassUnicode) -> CharClass {
+    let mut ccl = CharClass::new();
+
+    for range in class.iter() {
+        ccl.add(range.start()..=range.end());
+    }
+
+    ccl
+}
+
+/** Regular expression matched by Token::Regex.
+
+The regular expression is always matched anchored at the current reader position. A lazy DFA
+determines whether further input must be read to decide the longest possible match. */
+#[derive(Debug)]
+pub struct Regex {
+    pattern: String,                            // The regular expression's pattern
+    regex: meta::Regex,                         // Regex for matching with capture groups
+    dfa: Option<hybrid::dfa::DFA>,              // Lazy DFA to check for complete input
+    cache: RefCell<Option<hybrid::dfa::Cache>>, // Cache of the lazy DFA
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let regex = meta::Regex::new(pattern).map_err(|err| match err.syntax_error() {
+            Some(regex_syntax::Error::Parse(err)) => err.kind().to_string(),
+            Some(regex_syntax::Error::Translate(err)) => err.kind().to_string(),
+            _ => err.to_string(),
+        })?;
+
+        // A DFA is not available for every pattern, which requires to read all input then.
+        let dfa = hybrid::dfa::DFA::builder()
+            .configure(hybrid::dfa::Config::new().unicode_word_boundary(true))
+            .build(pattern)
+            .ok();
+        let cache = RefCell::new(dfa.as_ref().map(|dfa| dfa.create_cache()));
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+            dfa,
+            cache,
+        })
+    }
+
+    /// The regular expression's pattern.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Reads further input until no further input can change the match at the reader's position.
+    /// The lazy DFA keeps its state across reads, so that only newly read input is scanned.
+    fn read_complete(&self, reader: &mut Reader) {
+        let (dfa, mut cache) = match (&self.dfa, self.cache.borrow_mut()) {
+            (Some(dfa), cache) if cache.is_some() => (dfa, cache),
+            _ => {
+                while reader.extend() {}
+                return;
+            }
+        };
+        let cache = cache.as_mut().unwrap();
+
+        let mut state = match dfa.start_state_forward(
+            cache,
+            &Input::new(reader.buffered()).anchored(Anchored::Yes),
+        ) {
+            Ok(state) => state,
+            Err(_) => {
+                while reader.extend() {}
+                return;
+            }
+        };
+
+        let mut scanned = 0; // Input already fed into the DFA
+
+        loop {
+            let input = reader.buffered();
+
+            for byte in &input[scanned..] {
+                state = match dfa.next_state(cache, state, *byte) {
+                    Ok(state) if !state.is_quit() => state,
+                    _ => {
+                        while reader.extend() {}
+                        return;
+                    }
+                };
+
+                if state.is_dead() {
+                    return;
+                }
+            }
+
+            scanned = input.len();
+
+            if !reader.extend() {
+                return;
+            }
+        }
+    }
+}
+
+impl Clone for Regex {
+    fn clone(&self) -> Self {
+        Self {
+            pattern: self.pattern.clone(),
+            regex: self.regex.clone(),
+            dfa: self.dfa.clone(),
+            cache: RefCell::new(self.dfa.as_ref().map(|dfa| dfa.create_cache())),
+        }
+    }
+}
+
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl PartialOrd for Regex {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.pattern.partial_cmp(&other.pattern)
+    }
+}
+
+/// Callback function of a builtin token, which is identified by the token's name.
+#[derive(Debug, Clone, Copy)]
+pub struct CharFn {
+    pub name: &'static str,         // Name of the builtin token
+    pub func: fn(ch: char) -> bool, // Function testing a character
+}
+
+impl CharFn {
+    pub const fn new(name: &'static str, func: fn(ch: char) -> bool) -> Self {
+        Self { name, func }
+    }
+}
+
+impl PartialEq for CharFn {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl PartialOrd for CharFn {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.name.partial_cmp(other.name)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Token {
-    Void,                               // Matches the empty word
-    EOF,                                // Matches End of File
-    Char(CharClass),                    // Matches one character from a character class
-    BuiltinChar(fn(ch: char) -> bool),  // Matches one character from a callback function
-    Chars(CharClass),                   // Matches multiple characters from a character class
-    BuiltinChars(fn(ch: char) -> bool), // Matches multiple characters from a callback function
-    Match(String),                      // Match a string
-    Touch(String),                      // Match a string with zero severity
+    Void,                    // Matches the empty word
+    EOF,                     // Matches End of File
+    Char(CharClass),         // Matches one character from a character class
+    BuiltinChar(CharFn),     // Matches one character from a callback function
+    Chars(CharClass),        // Matches multiple characters from a character class
+    BuiltinChars(CharFn),    // Matches multiple characters from a callback function
+    Match(String),           // Match a string
+    Touch(String),           // Match a string with zero severity
+    MatchIgnoreCase(String), // Match a string case-insensitively
+    TouchIgnoreCase(String), // Match a string case-insensitively with zero severity
+    Byte(CharClass),         // Matches one byte from a character class up to \xff
+    Bytes(CharClass),        // Matches multiple bytes from a character class up to \xff
+    ByteMatch(Vec<u8>),      // Match a byte sequence
+    BinaryInt {
+        // Matches a fixed-width binary integer
+        width: u8,
+        signed: bool,
+        big_endian: bool,
+    },
+    Regex(Box<Regex>), // Matches a regular expression
+    Indent,            // Matches an increased indentation at the beginning of a line
+    Dedent,            // Matches a decreased indentation at the beginning of a line (zero-width)
+    Samedent,          // Matches the same indentation at the beginning of a line
+    BOL,               // Matches the beginning of a line (zero-width)
+    EOL,               // Matches the end of a line or input (zero-width)
+    Line,              // Matches the rest of a line, and consumes its line break
+}
+
+/// Consumes a line break, which is either "\n", "\r\n" or "\r".
+pub(crate) fn read_line_break(reader: &mut Reader) -> bool {
+    if reader.once(|ch| ch == '\n').is_some() {
+        true
+    } else if reader.once(|ch| ch == '\r').is_some() {
+        reader.once(|ch| ch == '\n');
+        true
+    } else {
+        false
+    }
+}
+
+/** Measures the indentation of the next non-blank line, starting at the reader's current offset.
+
+Returns the indentation's width, where a space or tab counts as one, and the offset behind the
+indentation, or None at the end of input. The reader's offset is not changed. */
+fn measure_indent(reader: &mut Reader) -> Option<(usize, Offset)> {
+    let start = reader.tell();
+
+    let ret = loop {
+        let mut width = 0;
+
+        while let Some(' ' | '\t') = reader.peek().copied() {
+            reader.next();
+            width += 1;
+        }
+
+        match reader.peek().copied() {
+            // Blank lines are skipped
+            Some('\n' | '\r') => {
+                reader.next();
+            }
+            Some(_) => break Some((width, reader.tell())),
+            None => break None,
+        }
+    };
+
+    reader.reset(start);
+    ret
 }
 
+/// Builtin tokens matching characters by a callback function.
+const BUILTIN_CHARS: &[CharFn] = &[
+    CharFn::new("Alphabetic", |c| c.is_alphabetic()),
+    CharFn::new("Alphanumeric", |c| c.is_alphanumeric()),
+    CharFn::new("Ascii", |c| c.is_ascii()),
+    CharFn::new("AsciiControl", |c| c.is_ascii_control()),
+    CharFn::new("AsciiPunctuation", |c| c.is_ascii_punctuation()),
+    CharFn::new("Control", |c| c.is_control()),
+    CharFn::new("Digit", |c| c.is_ascii_digit()),
+    CharFn::new("Lowercase", |c| c.is_lowercase()),
+    CharFn::new("Numeric", |c| c.is_numeric()),
+    CharFn::new("Uppercase", |c| c.is_uppercase()),
+    CharFn::new("Whitespace", |c| c.is_whitespace()),
+];
+
 impl Token {
     /// Retrieve builtin token
     pub fn builtin(ident: &str) -> Option<Token> {
         fn builtin_ccl(ident: &str) -> Option<Token> {
+            if let Some(f) = BUILTIN_CHARS.iter().find(|f| f.name == ident) {
+                return Some(Token::BuiltinChar(*f));
+            }
+
             Some(match ident {
-                "Alphabetic" => Token::BuiltinChar(|c| c.is_alphabetic()),
-                "Alphanumeric" => Token::BuiltinChar(|c| c.is_alphanumeric()),
-                "Ascii" => Token::BuiltinChar(|c| c.is_ascii()),
                 "AsciiAlphabetic" => Token::Char(charclass!['A' => 'Z', 'a' => 'z']),
                 "AsciiAlphanumeric" => Token::Char(charclass!['A' => 'Z', 'a' => 'z', '0' => '9']),
-                "AsciiControl" => Token::BuiltinChar(|c| c.is_ascii_control()),
                 "AsciiDigit" => Token::Char(charclass!['0' => '9']),
                 "AsciiGraphic" => Token::Char(charclass!['!' => '~']),
                 "AsciiHexdigit" => Token::Char(charclass!['0' => '9', 'A' => 'F', 'a' => 'f']),
                 "AsciiLowercase" => Token::Char(charclass!['a' => 'z']),
-                "AsciiPunctuation" => Token::BuiltinChar(|c| c.is_ascii_punctuation()),
                 "AsciiUppercase" => Token::Char(charclass!['A' => 'Z']),
-                "AsciiWhitespace" => Token::Char(charclass!['A' => 'Z', 'a' => 'z']),
-                "Control" => Token::BuiltinChar(|c| c.is_control()),
-                "Digit" => Token::BuiltinChar(|c| c.is_digit(10)),
-                "Lowercase" => Token::BuiltinChar(|c| c.is_lowercase()),
-                "Numeric" => Token::BuiltinChar(|c| c.is_numeric()),
-                "Uppercase" => Token::BuiltinChar(|c| c.is_uppercase()),
-                "Whitespace" => Token::BuiltinChar(|c| c.is_whitespace()),
+                "AsciiWhitespace" => Token::Char(charclass![' ', '\t', '\n', '\x0c', '\r']),
+
+                // Some common Unicode scripts and general categories; Any other Unicode property
+                // is only available as `\p{...}` in character classes, to not clash with names.
+                "Arabic" | "Cyrillic" | "Greek" | "Han" | "Hebrew" | "Latin" | "Ll" | "Lu"
+                | "Nd" => Token::Char(class_to_ccl(&unicode_property(ident).unwrap())),
 
                 // Any identifier attached with an "s" will be checked for Token+
                 ident if ident.len() > 1 && ident.ends_with("s") => {
-                    if let Some(Token::BuiltinChar(f)) = builtin_ccl(&ident[..ident.len() - 1]) {
-                        Token::BuiltinChars(f)
-                    } else {
-                        return None;
+                    match builtin_ccl(&ident[..ident.len() - 1]) {
+                        Some(Token::BuiltinChar(f)) => Token::BuiltinChars(f),
+                        Some(Token::Char(ccl)) => Token::Chars(ccl),
+                        _ => return None,
                     }
                 }
                 _ => return None,
             })
         }
 
+        fn builtin_int(ident: &str) -> Option<Token> {
+            let (signed, ident) = match ident.strip_prefix("Uint") {
+                Some(ident) => (false, ident),
+                None => (true, ident.strip_prefix("Int")?),
+            };
+
+            let (width, big_endian) = match ident {
+                "8" => (1, false),
+                "16le" => (2, false),
+                "16be" => (2, true),
+                "32le" => (4, false),
+                "32be" => (4, true),
+                "64le" => (8, false),
+                "64be" => (8, true),
+                _ => return None,
+            };
+
+            Some(Token::BinaryInt {
+                width,
+                signed,
+                big_endian,
+            })
+        }
+
         match ident {
             "Any" => Some(Token::any()),
+            "BOL" => Some(Token::BOL),
+            "Byte" => Some(Token::Byte(charclass!['\0' => '\u{ff}'])),
+            "Dedent" => Some(Token::Dedent),
             "EOF" => Some(Token::EOF),
+            "EOL" => Some(Token::EOL),
+            "Indent" => Some(Token::Indent),
+            "Line" => Some(Token::Line),
+            "Samedent" => Some(Token::Samedent),
             "Void" => Some(Token::Void),
+            ident if ident.starts_with("Int") || ident.starts_with("Uint") => builtin_int(ident),
             ident => builtin_ccl(ident),
         }
     }
@@ -80,9 +359,11 @@ impl Token {
                 }
             }
             Token::Char(ccl) => {
-                if let Some(ch) = reader.once(|ch| ccl.test(&(ch..=ch))) {
+                let start = reader.tell();
+
+                if reader.once(|ch| ccl.test(&(ch..=ch))).is_some() {
                     return Ok(Accept::Push(Capture::Range(
-                        reader.capture_last(ch.len_utf8()),
+                        reader.capture_from(&start),
                         None,
                         5,
                     )));
@@ -91,9 +372,11 @@ impl Token {
                 Err(Reject::Next)
             }
             Token::BuiltinChar(f) => {
-                if let Some(ch) = reader.once(f) {
+                let start = reader.tell();
+
+                if reader.once(f.func).is_some() {
                     return Ok(Accept::Push(Capture::Range(
-                        reader.capture_last(ch.len_utf8()),
+                        reader.capture_from(&start),
                         None,
                         5,
                     )));
@@ -125,7 +408,7 @@ impl Token {
                 let start = reader.tell();
 
                 while let Some(ch) = reader.peek() {
-                    if !f(*ch) {
+                    if !(f.func)(*ch) {
                         break;
                     }
 
@@ -144,25 +427,37 @@ impl Token {
             Token::Match(string) | Token::Touch(string) => {
                 let start = reader.tell();
 
-                for ch in string.chars() {
-                    if let Some(c) = reader.peek() {
-                        if *c != ch {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
+                if string.chars().all(|ch| reader.once(|c| c == ch).is_some()) {
+                    let range = reader.capture_from(&start);
 
-                    reader.next();
+                    Ok(Accept::Push(Capture::Range(
+                        range,
+                        None,
+                        if matches!(self, Token::Touch(_)) {
+                            0
+                        } else {
+                            5
+                        },
+                    )))
+                } else {
+                    reader.reset(start);
+                    Err(Reject::Next)
                 }
+            }
+            Token::MatchIgnoreCase(string) | Token::TouchIgnoreCase(string) => {
+                let start = reader.tell();
 
-                let range = reader.capture_from(&start);
+                if string.chars().all(|ch| {
+                    reader
+                        .once(|c| c == ch || c.to_lowercase().eq(ch.to_lowercase()))
+                        .is_some()
+                }) {
+                    let range = reader.capture_from(&start);
 
-                if range.len() == string.len() {
                     Ok(Accept::Push(Capture::Range(
                         range,
                         None,
-                        if matches!(self, Token::Touch(_)) {
+                        if matches!(self, Token::TouchIgnoreCase(_)) {
                             0
                         } else {
                             5
@@ -173,6 +468,216 @@ impl Token {
                     Err(Reject::Next)
                 }
             }
+            Token::Byte(ccl) => match reader.peek_byte() {
+                Some(byte) if ccl.test(&(byte as char..=byte as char)) => {
+                    let start = reader.tell();
+                    reader.next_byte();
+
+                    Ok(Accept::Push(Capture::Range(
+                        reader.capture_from(&start),
+                        None,
+                        5,
+                    )))
+                }
+                _ => Err(Reject::Next),
+            },
+            Token::Bytes(ccl) => {
+                let start = reader.tell();
+
+                while let Some(byte) = reader.peek_byte() {
+                    if !ccl.test(&(byte as char..=byte as char)) {
+                        break;
+                    }
+
+                    reader.next_byte();
+                }
+
+                let range = reader.capture_from(&start);
+
+                if !range.is_empty() {
+                    Ok(Accept::Push(Capture::Range(range, None, 5)))
+                } else {
+                    Err(Reject::Next)
+                }
+            }
+            Token::ByteMatch(bytes) => {
+                let start = reader.tell();
+
+                if bytes
+                    .iter()
+                    .all(|byte| reader.peek_byte() == Some(*byte) && reader.next_byte().is_some())
+                {
+                    Ok(Accept::Push(Capture::Range(
+                        reader.capture_from(&start),
+                        None,
+                        5,
+                    )))
+                } else {
+                    reader.reset(start);
+                    Err(Reject::Next)
+                }
+            }
+            Token::BinaryInt {
+                width,
+                signed,
+                big_endian,
+            } => {
+                if let Some(range) = reader.take_bytes(*width as usize) {
+                    let bytes = reader.get_bytes(&range);
+
+                    let value = match (signed, big_endian) {
+                        (true, true) => BigInt::from_signed_bytes_be(bytes),
+                        (true, false) => BigInt::from_signed_bytes_le(bytes),
+                        (false, true) => BigInt::from_bytes_be(Sign::Plus, bytes),
+                        (false, false) => BigInt::from_bytes_le(Sign::Plus, bytes),
+                    };
+
+                    Ok(Accept::Push(Capture::Value(crate::value!(value), None, 5)))
+                } else {
+                    Err(Reject::Next)
+                }
+            }
+            Token::BOL => {
+                if reader.tell().col == 1 {
+                    Ok(Accept::Next)
+                } else {
+                    Err(Reject::Next)
+                }
+            }
+            Token::EOL => match reader.peek() {
+                None | Some('\n' | '\r') => Ok(Accept::Next),
+                _ => Err(Reject::Next),
+            },
+            Token::Line => {
+                let start = reader.tell();
+
+                while reader.once(|ch| ch != '\n' && ch != '\r').is_some() {}
+
+                let range = reader.capture_from(&start);
+
+                if read_line_break(reader) || !range.is_empty() {
+                    Ok(Accept::Push(Capture::Range(range, None, 5)))
+                } else {
+                    Err(Reject::Next)
+                }
+            }
+            Token::Indent | Token::Samedent => {
+                if reader.tell().col != 1 {
+                    return Err(Reject::Next);
+                }
+
+                match measure_indent(reader) {
+                    Some((width, end))
+                        if (matches!(self, Token::Indent) && width > reader.indent())
+                            || (matches!(self, Token::Samedent) && width == reader.indent()) =>
+                    {
+                        let start = reader.tell();
+                        reader.reset(end);
+
+                        if matches!(self, Token::Indent) {
+                            reader.push_indent(width);
+                        }
+
+                        Ok(Accept::Push(Capture::Range(
+                            reader.capture_from(&start),
+                            None,
+                            0,
+                        )))
+                    }
+                    _ => Err(Reject::Next),
+                }
+            }
+            Token::Dedent => {
+                if reader.tell().col != 1 && !reader.eof() {
+                    return Err(Reject::Next);
+                }
+
+                // At the end of input, any indentation is closed
+                let (width, end) = measure_indent(reader).unwrap_or_else(|| (0, reader.tell()));
+
+                if width >= reader.indent() || !reader.pop_indent() {
+                    return Err(Reject::Next);
+                }
+
+                if width > reader.indent() {
+                    return Err(Reject::Error(Box::new(Error::new(
+                        Some(end),
+                        "Dedent doesn't match any outer indentation level".to_string(),
+                    ))));
+                }
+
+                Ok(Accept::Push(Capture::Empty))
+            }
+            Token::Regex(regex) => {
+                // Read further input until the match can't change anymore
+                regex.read_complete(reader);
+
+                let mut caps = regex.regex.create_captures();
+                regex.regex.search_captures(
+                    &Input::new(reader.buffered()).anchored(Anchored::Yes),
+                    &mut caps,
+                );
+
+                let span = match caps.get_match() {
+                    Some(m) => m.span(),
+                    None => return Err(Reject::Next),
+                };
+
+                let start = reader.tell();
+                reader.advance_to(start.offset + span.end);
+
+                // Without capture groups, the matched range is the result
+                if caps.group_len() == 1 {
+                    return Ok(Accept::Push(Capture::Range(
+                        reader.capture_from(&start),
+                        None,
+                        5,
+                    )));
+                }
+
+                let groups = (1..caps.group_len()).map(|i| {
+                    caps.get_group(i)
+                        .map(|span| {
+                            RefValue::from(
+                                reader
+                                    .get(&(start.offset + span.start..start.offset + span.end))
+                                    .into_owned(),
+                            )
+                        })
+                        .unwrap_or_else(|| crate::value!(void))
+                });
+
+                let names: Vec<Option<&str>> = regex
+                    .regex
+                    .group_info()
+                    .pattern_names(PatternID::ZERO)
+                    .skip(1)
+                    .collect();
+
+                // Named groups result in a dict, otherwise a list of the groups is returned
+                let value = if names.iter().any(|name| name.is_some()) {
+                    let mut dict = Dict::new();
+
+                    for (i, (name, value)) in names.iter().zip(groups).enumerate() {
+                        match name {
+                            Some(name) => dict.insert(name.to_string(), value),
+                            None => dict.insert((i + 1).to_string(), value),
+                        };
+                    }
+
+                    RefValue::from(dict)
+                } else {
+                    let mut list = List::new();
+
+                    for value in groups {
+                        list.push(value);
+                    }
+
+                    RefValue::from(list)
+                };
+
+                Ok(Accept::Push(Capture::Value(value, None, 5)))
+            }
         }
     }
 }
@@ -188,9 +693,43 @@ impl Object for Token {
             Token::EOF => "EOF".to_string(),
             Token::Char(ccl) => format!("{:?}", ccl),
             Token::Chars(ccl) => format!("{:?}+", ccl),
-            Token::BuiltinChar(_) | Token::BuiltinChars(_) => "\"<token builtin fn>\n".to_string(),
+            Token::BuiltinChar(_) | Token::BuiltinChars(_) => "<token builtin fn>".to_string(),
             Token::Touch(s) => format!("'{}'", s),
             Token::Match(s) => format!("''{}''", s),
+            Token::TouchIgnoreCase(s) => format!("'{}'i", s),
+            Token::MatchIgnoreCase(s) => format!("''{}''i", s),
+            Token::Byte(ccl) => format!("b{:?}", ccl),
+            Token::Bytes(ccl) => format!("b{:?}+", ccl),
+            Token::ByteMatch(bytes) => format!(
+                "b'{}'",
+                bytes
+                    .iter()
+                    .map(|byte| std::ascii::escape_default(*byte).to_string())
+                    .collect::<String>()
+            ),
+            Token::BinaryInt {
+                width,
+                signed,
+                big_endian,
+            } => format!(
+                "{}{}{}",
+                if *signed { "Int" } else { "Uint" },
+                *width as u32 * 8,
+                if *width == 1 {
+                    ""
+                } else if *big_endian {
+                    "be"
+                } else {
+                    "le"
+                }
+            ),
+            Token::Indent => "Indent".to_string(),
+            Token::Dedent => "Dedent".to_string(),
+            Token::Samedent => "Samedent".to_string(),
+            Token::BOL => "BOL".to_string(),
+            Token::EOL => "EOL".to_string(),
+            Token::Line => "Line".to_string(),
+            Token::Regex(regex) => format!("r'{}'", regex.pattern().replace('\'', "\\'")),
         }
     }
 
@@ -209,6 +748,15 @@ impl Object for Token {
             Token::Char(ccl) | Token::Chars(ccl) => ccl.len() == 0, //True shouldn't be possible here by definition!
             Token::BuiltinChar(_) | Token::BuiltinChars(_) => true,
             Token::Match(s) | Token::Touch(s) => s.len() == 0, //True shouldn't be possible here by definition!
+            Token::MatchIgnoreCase(s) | Token::TouchIgnoreCase(s) => s.is_empty(),
+            Token::Byte(ccl) | Token::Bytes(ccl) => ccl.len() == 0,
+            Token::BinaryInt { .. } => false,
+            Token::ByteMatch(bytes) => bytes.is_empty(),
+            Token::Regex(regex) => regex.regex.is_match(""),
+            Token::Indent => false,
+            Token::Dedent | Token::Samedent => true,
+            Token::BOL | Token::EOL => true,
+            Token::Line => false,
         }
     }
 
@@ -219,7 +767,15 @@ impl Object for Token {
         nargs: Option<Dict>,
     ) -> Result<Accept, Reject> {
         assert!(args == 0 && nargs.is_none());
-        self.read(context.runtime.reader)
+
+        let start = context.runtime.reader.tell();
+        let result = self.read(context.runtime.reader);
+
+        if let Err(Reject::Next) = result {
+            context.runtime.reject_at(&start, || self.repr());
+        }
+
+        result
     }
 }
 
@@ -346,6 +902,258 @@ tokay_token!("Word(min=1 max=void)", {
     }
 });
 
+// Scanning up to a terminator; With include=true, the terminator is consumed and part of the range.
+tokay_token!("Until(term, include=true)", {
+    let term = term.to_string();
+
+    let first = match term.chars().next() {
+        Some(first) => first,
+        None => return Err(format!("{} term must not be empty", __function).into()),
+    };
+
+    let reader = &mut context.runtime.reader;
+    let start = reader.tell();
+
+    loop {
+        // Quickly skip anything that can't start the terminator
+        while reader.once(|ch| ch != first).is_some() {}
+
+        let end = reader.tell();
+
+        if reader.peek().is_none() {
+            reader.reset(start);
+            return Err(Reject::Next);
+        }
+
+        if term.chars().all(|ch| reader.once(|c| c == ch).is_some()) {
+            if !include.is_true() {
+                reader.reset(end);
+            }
+
+            return Ok(Accept::Push(Capture::Range(
+                reader.capture_from(&start),
+                None,
+                5,
+            )));
+        }
+
+        reader.reset(end);
+        reader.next();
+    }
+});
+
+// Scanning nested, balanced delimiters; The range enclosed by the outermost delimiters is returned.
+tokay_token!("Balanced(open, close, escape=void)", {
+    let open = open.to_string();
+    let close = close.to_string();
+    let escape = if escape.is_void() {
+        None
+    } else {
+        Some(escape.to_string())
+    };
+
+    if open.is_empty() || close.is_empty() || escape.as_ref().is_some_and(|e| e.is_empty()) {
+        return Err(format!("{} delimiters must not be empty", __function).into());
+    }
+
+    let reader = &mut context.runtime.reader;
+    let start = reader.tell();
+
+    // Consume string when it is at the current offset
+    fn consume(reader: &mut Reader, string: &str) -> bool {
+        let start = reader.tell();
+
+        if string.chars().all(|ch| reader.once(|c| c == ch).is_some()) {
+            true
+        } else {
+            reader.reset(start);
+            false
+        }
+    }
+
+    if !consume(reader, &open) {
+        return Err(Reject::Next);
+    }
+
+    let inner = reader.tell().offset;
+    let mut depth = 1;
+
+    loop {
+        let end = reader.tell().offset;
+
+        if let Some(escape) = &escape {
+            if consume(reader, escape) {
+                reader.next();
+                continue;
+            }
+        }
+
+        // Close is checked first, so equal delimiters don't nest
+        if consume(reader, &close) {
+            depth -= 1;
+
+            if depth == 0 {
+                return Ok(Accept::Push(Capture::Range(inner..end, None, 5)));
+            }
+        } else if consume(reader, &open) {
+            depth += 1;
+        } else if reader.next().is_none() {
+            reader.reset(start);
+            return Err(Reject::Next);
+        }
+    }
+});
+
+// Lines, without their line breaks; Reads any remaining lines, or exactly count lines.
+tokay_token!("Lines(count=void)", {
+    let count = if count.is_void() {
+        None
+    } else {
+        Some(count.to_usize()?)
+    };
+
+    let reader = &mut context.runtime.reader;
+    let start = reader.tell();
+    let mut lines = List::new();
+
+    while count.is_none_or(|count| lines.len() < count) {
+        match Token::Line.read(reader) {
+            Ok(Accept::Push(Capture::Range(range, ..))) => {
+                lines.push(RefValue::from(reader.get(&range).into_owned()))
+            }
+            _ => break,
+        }
+    }
+
+    if lines.is_empty() || count.is_some_and(|count| lines.len() < count) {
+        reader.reset(start);
+        return Err(Reject::Next);
+    }
+
+    Ok(Accept::Push(Capture::Value(RefValue::from(lines), None, 5)))
+});
+
+/** Decodes an escape sequence behind a backslash, exactly like Tokay's parser does in string literals.
+
+Returns None at the end of input. */
+fn read_escape_sequence(reader: &mut Reader) -> Option<char> {
+    // Read exactly count digits of radix, or nothing
+    fn digits(reader: &mut Reader, count: usize, radix: u32) -> Option<String> {
+        let start = reader.tell();
+        let mut digits = String::new();
+
+        while digits.len() < count {
+            match reader.once(|ch| ch.is_digit(radix)) {
+                Some(ch) => digits.push(ch),
+                None => {
+                    reader.reset(start);
+                    return None;
+                }
+            }
+        }
+
+        Some(digits)
+    }
+
+    let start = reader.tell();
+
+    Some(match reader.next()? {
+        'a' => '\x07',
+        'b' => '\x08',
+        'f' => '\x0c',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'v' => '\x0b',
+        ch @ '0'..='7' => {
+            reader.reset(start);
+
+            match digits(reader, 3, 8) {
+                Some(digits) => code_to_char(&digits, 8),
+                None => reader.next().unwrap_or(ch),
+            }
+        }
+        'x' => digits(reader, 2, 16).map_or('x', |digits| code_to_char(&digits, 16)),
+        'u' => {
+            let braced = reader.tell();
+
+            if reader.once(|ch| ch == '{').is_some() {
+                if let Some(digits) = reader.span(|ch| ch.is_ascii_hexdigit()) {
+                    let digits = digits.into_owned();
+
+                    if reader.once(|ch| ch == '}').is_some() {
+                        return Some(code_to_char(&digits, 16));
+                    }
+                }
+
+                reader.reset(braced);
+            }
+
+            digits(reader, 4, 16).map_or('u', |digits| code_to_char(&digits, 16))
+        }
+        'U' => digits(reader, 8, 16).map_or('U', |digits| code_to_char(&digits, 16)),
+        ch => ch,
+    })
+}
+
+// Quoted strings, with escape sequences decoded like in Tokay's own string literals.
+// The quote defaults to '"'. With escapes=false, escape sequences are kept undecoded, but still
+// protect the quote. With raw=true, backslashes have no special meaning at all.
+tokay_token!("String(quote=void, escapes=true, raw=false)", {
+    let quote = if quote.is_void() {
+        '"'
+    } else {
+        let quote = quote.to_string();
+        let mut chars = quote.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => ch,
+            _ => return Err(format!("{} quote must be a single character", __function).into()),
+        }
+    };
+
+    let escapes = escapes.is_true();
+    let raw = raw.is_true();
+
+    let reader = &mut context.runtime.reader;
+    let start = reader.tell();
+
+    if reader.once(|ch| ch == quote).is_none() {
+        return Err(Reject::Next);
+    }
+
+    let mut string = String::new();
+
+    loop {
+        match reader.next() {
+            Some(ch) if ch == quote => break,
+            Some('\\') if !raw => {
+                if escapes {
+                    if let Some(ch) = read_escape_sequence(reader) {
+                        string.push(ch);
+                    }
+                } else {
+                    string.push('\\');
+
+                    if let Some(ch) = reader.next() {
+                        string.push(ch);
+                    }
+                }
+            }
+            Some(ch) => string.push(ch),
+            None => {
+                return Error::new(
+                    Some(start),
+                    format!("Unclosed string, expecting '{}'", quote),
+                )
+                .into()
+            }
+        }
+    }
+
+    Ok(Accept::Push(Capture::Value(crate::value!(string), None, 5)))
+});
+
 #[test]
 #[allow(non_snake_case)]
 // Test for built-in tokens
@@ -425,3 +1233,161 @@ fn builtin_tokens_Float() {
         ])))
     );
 }
+
+#[test]
+#[allow(non_snake_case)]
+// Test for built-in token String
+fn builtin_tokens_String() {
+    // Escape sequences are decoded exactly like in Tokay's string literals
+    for string in [
+        r#"a\tb\x41\101\u0041\U0001F600\u{1F600}\q\"\\"#,
+        r#"\x4\u{zz}\u{41\U12\8\777\a\b\f\n\r\v"#,
+    ] {
+        let string = format!("\"{}\"", string);
+        assert_eq!(crate::run("String", &string), crate::run(&string, ""));
+    }
+
+    assert_eq!(
+        crate::run("String(\"'\")", r#"'it\'s' "no""#),
+        Ok(Some(crate::value!("it's")))
+    );
+    assert_eq!(
+        crate::run("String(escapes=false)", r#""a\"b\n""#),
+        Ok(Some(crate::value!(r#"a\"b\n"#)))
+    );
+    assert_eq!(
+        crate::run("String(raw=true)", r#""a\" b"#),
+        Ok(Some(crate::value!(r#"a\"#)))
+    );
+
+    // Unclosed strings are reported at their beginning
+    assert_eq!(
+        crate::run("String", "x \"abc"),
+        Err("Line 1, column 3: Unclosed string, expecting '\"'".to_string())
+    );
+    assert_eq!(
+        crate::run("String(\"ab\")", "\"abc\""),
+        Err("Line 1, column 1: String() quote must be a single character".to_string())
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+// Test for built-in tokens Until and Balanced
+fn builtin_tokens_Until_Balanced() {
+    assert_eq!(
+        crate::run("'/*' Until(\"*/\")", "a /* x */ b /**/ c /* d"),
+        Ok(Some(crate::value!([" x */", "*/"])))
+    );
+
+    assert_eq!(
+        crate::run("Until(\";\", include=false) ';'", "a = 1; ; b;c"),
+        Ok(Some(crate::value!(["a = 1", " ", " b"])))
+    );
+
+    assert_eq!(
+        crate::run("Balanced(\"(\", \")\")", "f(a, (b), c) g(x"),
+        Ok(Some(crate::value!("a, (b), c")))
+    );
+
+    assert_eq!(
+        crate::run("Balanced(\"begin\", \"end\")", "begin a begin b end end"),
+        Ok(Some(crate::value!(" a begin b end ")))
+    );
+
+    assert_eq!(
+        crate::run("Balanced(\"'\", \"'\", escape=\"\\\\\")", "'it\\'s' 'x'"),
+        Ok(Some(crate::value!(["it\\'s", "x"])))
+    );
+}
+
+#[test]
+// Test for line anchors and line-oriented tokens
+fn builtin_tokens_lines() {
+    let s = "ab cd\r\nef gh\rij\n\nkl";
+
+    assert_eq!(
+        crate::run("BOL Word", s),
+        Ok(Some(crate::value!(["ab", "ef", "ij", "kl"])))
+    );
+    assert_eq!(
+        crate::run("Word EOL", s),
+        Ok(Some(crate::value!(["cd", "gh", "ij", "kl"])))
+    );
+    assert_eq!(
+        crate::run("Line", s),
+        Ok(Some(crate::value!(["ab cd", "ef gh", "ij", "", "kl"])))
+    );
+    assert_eq!(
+        crate::run("Lines(2)", s),
+        Ok(Some(crate::value!([["ab cd", "ef gh"], ["ij", ""]])))
+    );
+
+    // Any kind of line break is counted in rows
+    assert_eq!(
+        crate::run("Line Line Line error(\"here\")", s),
+        Err("Line 4, column 1: here".to_string())
+    );
+}
+
+#[test]
+// Test for binary reader mode and byte-level tokens
+fn builtin_tokens_binary() {
+    use crate::reader::Reader;
+
+    let run = |src: &str, input: &'static [u8], binary: bool| {
+        let mut compiler = crate::Compiler::new(true);
+        compiler.compile_from_str(src).unwrap();
+        let program = compiler.finalize().unwrap();
+
+        let input = Box::new(std::io::Cursor::new(input));
+
+        program.run_from_reader(if binary {
+            Reader::new_binary(input)
+        } else {
+            Reader::new(input)
+        })
+    };
+
+    let frame: &'static [u8] = b"\x01\xff\xfe\x34\x12\x00\x00\xfe\xff\xff\xffOK\xc3";
+
+    // Fixed-width integers
+    assert_eq!(
+        run("Uint8 Int16be Uint32le Int32le", frame, true),
+        Ok(Some(crate::value!([1, (-2), 0x1234, (-2)])))
+    );
+
+    assert_eq!(
+        run("Int8 Uint16be Int64le", frame, true),
+        Ok(Some(crate::value!([1, 0xfffe, (-8589929932i64)])))
+    );
+
+    // Bytes in binary mode are read as characters
+    assert_eq!(
+        run("Byte+", b"\x00\x7f\x80\xff", true),
+        Ok(Some(crate::value!(["\x00", "\x7f", "\u{80}", "\u{ff}"])))
+    );
+
+    // Byte literals and byte classes, also in text mode
+    for binary in [true, false] {
+        assert_eq!(
+            run("b'OK' [\\x80-\\xff]b Byte '!'", b"OK\xc3\xa4!", binary),
+            Ok(Some(crate::value!(["OK", "\u{c3}", "\u{a4}"])))
+        );
+    }
+
+    assert_eq!(
+        run(
+            "[^\\x00-\\x7f]b+ 'X'",
+            b"\xc3\xa4\xc3\xb6\xc3\xbcX\xe2\x82\xacX",
+            false
+        ),
+        Ok(Some(crate::value!(["äöü", "€"])))
+    );
+
+    // Characters beyond \xff are not bytes
+    assert_eq!(
+        crate::run("b'\\u20ac'", ""),
+        Err("Line 1, column 1: Byte literal may only contain characters up to '\\xff'".to_string())
+    );
+}
@@ -192,6 +192,14 @@ impl Object for Value {
         }
     }
 
+    fn attr(&self, name: &str) -> Option<RefValue> {
+        if let Self::Object(object) = self {
+            object.attr(name)
+        } else {
+            None
+        }
+    }
+
     fn call(
         &self,
         context: &mut Context,
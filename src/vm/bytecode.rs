@@ -0,0 +1,892 @@
+//! Serialization of compiled programs into a versioned binary format.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::*;
+use crate::builtin::{Builtin, BuiltinRef};
+use crate::reader::Offset;
+use crate::value::token::{CharFn, Regex};
+use crate::value::{Dict, List, Object, Parselet, ParseletRef, RefValue, Str, Token, Value};
+use charclass::CharClass;
+use num_bigint::BigInt;
+
+/// Magic bytes every bytecode file starts with.
+pub const BYTECODE_MAGIC: &[u8] = b"TOKAY\0BC";
+
+// Version of the bytecode format, to be increased on any change of the format or the operations
+const BYTECODE_VERSION: u32 = 4;
+
+// Operators used by Op::UnaryOp and Op::BinaryOp
+const OPERATORS: &[&str] = &[
+    "add", "sub", "mul", "div", "iadd", "isub", "imul", "idiv", "iinc", "idec", "not", "neg", "eq",
+    "neq", "lteq", "gteq", "lt", "gt",
+];
+
+// Maximum nesting depth of list and dict values
+const MAX_DEPTH: usize = 256;
+
+/// Serializes the statics of a program into bytecode.
+pub(crate) fn encode(statics: &[RefValue]) -> Result<Vec<u8>, String> {
+    let mut enc = Encoder {
+        bytes: BYTECODE_MAGIC.to_vec(),
+        parselets: HashMap::new(),
+    };
+
+    BYTECODE_VERSION.encode(&mut enc)?;
+    env!("CARGO_PKG_VERSION").to_string().encode(&mut enc)?;
+    statics.to_vec().encode(&mut enc)?;
+
+    Ok(enc.bytes)
+}
+
+/// Deserializes the statics of a program from bytecode.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Vec<RefValue>, String> {
+    let bytes = bytes
+        .strip_prefix(BYTECODE_MAGIC)
+        .ok_or("Not a Tokay bytecode file")?;
+
+    let mut dec = Decoder {
+        bytes,
+        pos: 0,
+        parselets: Vec::new(),
+        sources: HashMap::new(),
+        depth: 0,
+    };
+
+    let version = u32::decode(&mut dec)?;
+    if version != BYTECODE_VERSION {
+        return Err(format!("Unsupported bytecode version {}", version));
+    }
+
+    // Operations may change between releases, so bytecode is only accepted from the same release
+    let release = String::decode(&mut dec)?;
+    if release != env!("CARGO_PKG_VERSION") {
+        return Err(format!(
+            "Bytecode was compiled by Tokay {}, but this is Tokay {}",
+            release,
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
+
+    let statics = Vec::<RefValue>::decode(&mut dec)?;
+
+    if dec.pos < dec.bytes.len() {
+        return Err("Trailing data in bytecode".to_string());
+    }
+
+    // Globals are the locals of the main parselet, which is the last parselet in the statics
+    let globals = statics
+        .iter()
+        .rev()
+        .find_map(|value| {
+            value
+                .borrow()
+                .object::<ParseletRef>()
+                .map(|parselet| parselet.0.borrow().locals)
+        })
+        .unwrap_or(0);
+
+    for parselet in &dec.parselets {
+        let parselet = parselet.0.borrow();
+
+        for ops in [&parselet.begin, &parselet.end, &parselet.body] {
+            if !check_ops(ops, statics.len(), parselet.locals, globals) {
+                return Err(format!("Invalid address in parselet '{}'", parselet.name));
+            }
+        }
+    }
+
+    Ok(statics)
+}
+
+// Checks that every static, local, global and jump address of the operations is in range
+fn check_ops(ops: &[Op], statics: usize, locals: usize, globals: usize) -> bool {
+    ops.iter().enumerate().all(|(ip, op)| match op {
+        Op::CallStatic(addr) | Op::LoadStatic(addr) => *addr < statics,
+        Op::CallStaticArg(addr_args) | Op::CallStaticArgNamed(addr_args) => addr_args.0 < statics,
+        Op::LoadFast(addr) | Op::StoreFast(addr) | Op::StoreFastHold(addr) => *addr < locals,
+        Op::LoadGlobal(addr) | Op::StoreGlobal(addr) | Op::StoreGlobalHold(addr) => *addr < globals,
+        Op::Frame(goto)
+        | Op::Fuse(goto)
+        | Op::Try(goto)
+        | Op::Loop(goto)
+        | Op::ForwardIfTrue(goto)
+        | Op::ForwardIfFalse(goto)
+        | Op::ForwardIfConsumed(goto)
+        | Op::Forward(goto) => *goto <= ops.len() - ip,
+        Op::BackwardIfTrue(goto)
+        | Op::BackwardIfFalse(goto)
+        | Op::BackwardIfConsumed(goto)
+        | Op::Backward(goto) => *goto <= ip,
+        _ => true,
+    })
+}
+
+// Encoder state, which writes every parselet only once
+struct Encoder {
+    bytes: Vec<u8>,
+    parselets: HashMap<usize, usize>, // parselet addresses to their index
+}
+
+// Decoder state
+struct Decoder<'bytes> {
+    bytes: &'bytes [u8],
+    pos: usize,
+    parselets: Vec<ParseletRef>,       // parselets decoded so far
+    sources: HashMap<String, Rc<str>>, // source names shared among offsets
+    depth: usize,                      // nesting depth of the value currently decoded
+}
+
+impl<'bytes> Decoder<'bytes> {
+    fn take(&mut self, len: usize) -> Result<&'bytes [u8], String> {
+        if len > self.bytes.len() - self.pos {
+            return Err("Unexpected end of bytecode".to_string());
+        }
+
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+}
+
+// Trait for types which can be written to and read from bytecode
+trait Bytecode: Sized {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String>;
+    fn decode(dec: &mut Decoder) -> Result<Self, String>;
+}
+
+impl Bytecode for u8 {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        enc.bytes.push(*self);
+        Ok(())
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(dec.take(1)?[0])
+    }
+}
+
+impl Bytecode for bool {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        (*self as u8).encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        match u8::decode(dec)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err("Invalid bool in bytecode".to_string()),
+        }
+    }
+}
+
+// Integers are written as unsigned LEB128
+impl Bytecode for u64 {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        let mut value = *self;
+
+        while value >= 0x80 {
+            enc.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+
+        enc.bytes.push(value as u8);
+        Ok(())
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let mut value = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = u8::decode(dec)?;
+
+            if shift > 63 {
+                return Err("Integer overflow in bytecode".to_string());
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+
+            shift += 7;
+        }
+    }
+}
+
+impl Bytecode for u32 {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        (*self as u64).encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        u32::try_from(u64::decode(dec)?).map_err(|err| err.to_string())
+    }
+}
+
+impl Bytecode for usize {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        (*self as u64).encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        usize::try_from(u64::decode(dec)?).map_err(|err| err.to_string())
+    }
+}
+
+impl Bytecode for char {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        (*self as u32).encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        char::from_u32(u32::decode(dec)?).ok_or("Invalid char in bytecode".to_string())
+    }
+}
+
+impl Bytecode for Vec<u8> {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        self.len().encode(enc)?;
+        enc.bytes.extend(self);
+        Ok(())
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let len = usize::decode(dec)?;
+        Ok(dec.take(len)?.to_vec())
+    }
+}
+
+impl Bytecode for String {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        self.as_bytes().to_vec().encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        String::from_utf8(Vec::<u8>::decode(dec)?).map_err(|err| err.to_string())
+    }
+}
+
+impl<T: Bytecode> Bytecode for Option<T> {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        match self {
+            None => 0u8.encode(enc),
+            Some(value) => {
+                1u8.encode(enc)?;
+                value.encode(enc)
+            }
+        }
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(if bool::decode(dec)? {
+            Some(T::decode(dec)?)
+        } else {
+            None
+        })
+    }
+}
+
+impl<T: Bytecode> Bytecode for Box<T> {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        self.as_ref().encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Box::new(T::decode(dec)?))
+    }
+}
+
+impl<A: Bytecode, B: Bytecode> Bytecode for (A, B) {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        self.0.encode(enc)?;
+        self.1.encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok((A::decode(dec)?, B::decode(dec)?))
+    }
+}
+
+// Vec<u8> is written as a byte string, so other vectors are implemented for each item type
+macro_rules! bytecode_vec {
+    ($($type:ty),*) => {
+        $(
+            impl Bytecode for Vec<$type> {
+                fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+                    self.len().encode(enc)?;
+
+                    for item in self {
+                        item.encode(enc)?;
+                    }
+
+                    Ok(())
+                }
+
+                fn decode(dec: &mut Decoder) -> Result<Self, String> {
+                    // Every item takes at least one byte, which bounds the preallocation
+                    let len = usize::decode(dec)?.min(dec.bytes.len() - dec.pos);
+                    let mut items = Vec::with_capacity(len);
+
+                    for _ in 0..len {
+                        items.push(<$type>::decode(dec)?);
+                    }
+
+                    Ok(items)
+                }
+            }
+        )*
+    };
+}
+
+bytecode_vec!(
+    RefValue,
+    Op,
+    (String, Option<usize>),
+    (String, RefValue),
+    (char, char)
+);
+
+impl Bytecode for &'static str {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        self.to_string().encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let name = String::decode(dec)?;
+
+        OPERATORS
+            .iter()
+            .find(|op| **op == name)
+            .copied()
+            .ok_or(format!("Unknown operator '{}' in bytecode", name))
+    }
+}
+
+impl Bytecode for Offset {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        self.source
+            .as_ref()
+            .map(|source| source.to_string())
+            .encode(enc)?;
+        self.offset.encode(enc)?;
+        self.row.encode(enc)?;
+        self.col.encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let source = Option::<String>::decode(dec)?.map(|source| {
+            dec.sources
+                .entry(source)
+                .or_insert_with_key(|source| Rc::from(source.as_str()))
+                .clone()
+        });
+
+        Ok(Offset {
+            source,
+            offset: usize::decode(dec)?,
+            row: u32::decode(dec)?,
+            col: u32::decode(dec)?,
+            indent: None,
+        })
+    }
+}
+
+/** Retrieves the ranges of a character class.
+
+CharClass doesn't provide its ranges, so they are probed. Ranges of characters in the class are
+found by a binary search with CharClass::test(). Gaps between them are found by adding probe ranges
+to a copy of the class, and checking whether all of their characters were added. */
+fn ccl_ranges(ccl: &CharClass) -> Vec<(char, char)> {
+    // Surrogates are mapped to the last character before them
+    fn ch(n: u32) -> char {
+        char::from_u32(n).unwrap_or('\u{d7ff}')
+    }
+
+    // Finds the last n in lo..=hi where monotonic f(n) holds, when f(lo) holds
+    fn last(mut lo: u32, mut hi: u32, f: impl Fn(u32) -> bool) -> u32 {
+        while lo < hi {
+            let mid = lo + (hi - lo + 1) / 2;
+
+            if f(mid) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+
+        lo
+    }
+
+    let max = char::MAX as u32;
+    let mut ranges = Vec::new();
+    let mut start = 0;
+
+    while start <= max {
+        if (0xd800..0xe000).contains(&start) {
+            start = 0xe000;
+        }
+
+        if ccl.test(&(ch(start)..=ch(start))) {
+            let end = last(start, max, |n| ccl.test(&(ch(start)..=ch(n))));
+            ranges.push((ch(start), ch(end)));
+            start = end + 1;
+        } else {
+            let absent = |n: u32| {
+                let mut probe = ccl.clone();
+                probe.add(ch(start)..=ch(n)) == ch(n) as u32 - start + 1
+            };
+
+            // Probe for the end of the gap with exponentially growing ranges first, so that
+            // probe ranges don't overlap too many ranges of the class.
+            let mut lo = start;
+            let mut step = 1;
+
+            let hi = loop {
+                let hi = start.saturating_add(step).min(max);
+
+                if !absent(hi) {
+                    break hi - 1;
+                } else if hi == max {
+                    return ranges;
+                }
+
+                lo = hi;
+                step *= 2;
+            };
+
+            start = last(lo, hi, absent) + 1;
+        }
+    }
+
+    ranges
+}
+
+impl Bytecode for CharClass {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        ccl_ranges(self).encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let mut ccl = CharClass::new();
+
+        for (start, end) in Vec::<(char, char)>::decode(dec)? {
+            ccl.add(start..=end);
+        }
+
+        Ok(ccl)
+    }
+}
+
+impl Bytecode for Token {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        match self {
+            Token::Void => 0u8.encode(enc),
+            Token::EOF => 1u8.encode(enc),
+            Token::Char(ccl) => {
+                2u8.encode(enc)?;
+                ccl.encode(enc)
+            }
+            Token::BuiltinChar(f) => {
+                3u8.encode(enc)?;
+                f.name.encode(enc)
+            }
+            Token::Chars(ccl) => {
+                4u8.encode(enc)?;
+                ccl.encode(enc)
+            }
+            Token::BuiltinChars(f) => {
+                5u8.encode(enc)?;
+                f.name.encode(enc)
+            }
+            Token::Match(string) => {
+                6u8.encode(enc)?;
+                string.encode(enc)
+            }
+            Token::Touch(string) => {
+                7u8.encode(enc)?;
+                string.encode(enc)
+            }
+            Token::MatchIgnoreCase(string) => {
+                8u8.encode(enc)?;
+                string.encode(enc)
+            }
+            Token::TouchIgnoreCase(string) => {
+                9u8.encode(enc)?;
+                string.encode(enc)
+            }
+            Token::Byte(ccl) => {
+                10u8.encode(enc)?;
+                ccl.encode(enc)
+            }
+            Token::Bytes(ccl) => {
+                11u8.encode(enc)?;
+                ccl.encode(enc)
+            }
+            Token::ByteMatch(bytes) => {
+                12u8.encode(enc)?;
+                bytes.encode(enc)
+            }
+            Token::BinaryInt {
+                width,
+                signed,
+                big_endian,
+            } => {
+                13u8.encode(enc)?;
+                width.encode(enc)?;
+                signed.encode(enc)?;
+                big_endian.encode(enc)
+            }
+            Token::Regex(regex) => {
+                14u8.encode(enc)?;
+                regex.pattern().to_string().encode(enc)
+            }
+            Token::Indent => 15u8.encode(enc),
+            Token::Dedent => 16u8.encode(enc),
+            Token::Samedent => 17u8.encode(enc),
+            Token::BOL => 18u8.encode(enc),
+            Token::EOL => 19u8.encode(enc),
+            Token::Line => 20u8.encode(enc),
+        }
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        // Builtin callback functions are looked up by their name
+        fn builtin_char(dec: &mut Decoder) -> Result<CharFn, String> {
+            let name = String::decode(dec)?;
+
+            match Token::builtin(&name) {
+                Some(Token::BuiltinChar(f)) => Ok(f),
+                _ => Err(format!("Unknown builtin token '{}' in bytecode", name)),
+            }
+        }
+
+        Ok(match u8::decode(dec)? {
+            0 => Token::Void,
+            1 => Token::EOF,
+            2 => Token::Char(CharClass::decode(dec)?),
+            3 => Token::BuiltinChar(builtin_char(dec)?),
+            4 => Token::Chars(CharClass::decode(dec)?),
+            5 => Token::BuiltinChars(builtin_char(dec)?),
+            6 => Token::Match(String::decode(dec)?),
+            7 => Token::Touch(String::decode(dec)?),
+            8 => Token::MatchIgnoreCase(String::decode(dec)?),
+            9 => Token::TouchIgnoreCase(String::decode(dec)?),
+            10 => Token::Byte(CharClass::decode(dec)?),
+            11 => Token::Bytes(CharClass::decode(dec)?),
+            12 => Token::ByteMatch(Vec::<u8>::decode(dec)?),
+            13 => Token::BinaryInt {
+                width: u8::decode(dec)?,
+                signed: bool::decode(dec)?,
+                big_endian: bool::decode(dec)?,
+            },
+            14 => Token::Regex(Box::new(Regex::new(&String::decode(dec)?)?)),
+            15 => Token::Indent,
+            16 => Token::Dedent,
+            17 => Token::Samedent,
+            18 => Token::BOL,
+            19 => Token::EOL,
+            20 => Token::Line,
+            tag => return Err(format!("Invalid token {} in bytecode", tag)),
+        })
+    }
+}
+
+/*
+    Every operation is identified by its tag, followed by its arguments.
+    The tags must be kept stable within a bytecode version.
+*/
+macro_rules! bytecode_op {
+    ($($tag:literal => $op:ident $(($($arg:ident: $type:ty),+))?),* $(,)?) => {
+        impl Bytecode for Op {
+            fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+                match self {
+                    $(
+                        Op::$op $(($($arg),+))? => {
+                            ($tag as u8).encode(enc)?;
+                            $($($arg.encode(enc)?;)+)?
+                            Ok(())
+                        }
+                    )*
+                    Op::Rust(_) => Err("Can't serialize native Rust operation".to_string()),
+                }
+            }
+
+            fn decode(dec: &mut Decoder) -> Result<Self, String> {
+                Ok(match u8::decode(dec)? {
+                    $($tag => Op::$op $(($(<$type>::decode(dec)?),+))?,)*
+                    tag => return Err(format!("Invalid operation {} in bytecode", tag)),
+                })
+            }
+        }
+    };
+}
+
+bytecode_op! {
+    0 => Nop,
+    1 => Offset(offset: Box<Offset>),
+    2 => Frame(fuse: usize),
+    3 => Commit,
+    4 => Reset,
+    5 => Close,
+    6 => Collect(collect: u8, push: u8),
+    7 => Fuse(addr: usize),
+    8 => Try(addr: usize),
+    9 => Cut,
+    10 => Loop(size: usize),
+    11 => Break,
+    12 => LoadBreak,
+    13 => Continue,
+    14 => ForwardIfTrue(addr: usize),
+    15 => ForwardIfFalse(addr: usize),
+    16 => ForwardIfConsumed(addr: usize),
+    17 => BackwardIfTrue(addr: usize),
+    18 => BackwardIfFalse(addr: usize),
+    19 => BackwardIfConsumed(addr: usize),
+    20 => Forward(addr: usize),
+    21 => Backward(addr: usize),
+    22 => Skip,
+    23 => Next,
+    24 => Push,
+    25 => LoadPush,
+    26 => Accept,
+    27 => LoadAccept,
+    28 => Repeat,
+    29 => LoadRepeat,
+    30 => Reject,
+    31 => LoadExit,
+    32 => Exit,
+    33 => Error(msg: Option<String>),
+    34 => Recover,
+    35 => CallOrCopy,
+    36 => Call,
+    37 => CallArg(args: usize),
+    38 => CallArgNamed(args: usize),
+    39 => CallStatic(addr: usize),
+    40 => CallStaticArg(addr_args: Box<(usize, usize)>),
+    41 => CallStaticArgNamed(addr_args: Box<(usize, usize)>),
+    42 => LoadStatic(addr: usize),
+    43 => Push0,
+    44 => Push1,
+    45 => PushVoid,
+    46 => PushNull,
+    47 => PushTrue,
+    48 => PushFalse,
+    49 => LoadGlobal(addr: usize),
+    50 => LoadFast(addr: usize),
+    51 => LoadFastCapture(index: usize),
+    52 => LoadCapture,
+    53 => LoadAttr,
+    54 => LoadIndex,
+    55 => StoreGlobal(addr: usize),
+    56 => StoreGlobalHold(addr: usize),
+    57 => StoreFast(addr: usize),
+    58 => StoreFastHold(addr: usize),
+    59 => StoreFastCapture(index: usize),
+    60 => StoreFastCaptureHold(index: usize),
+    61 => StoreCapture,
+    62 => StoreCaptureHold,
+    63 => StoreIndex,
+    64 => StoreIndexHold,
+    65 => MakeAlias,
+    66 => MakeList(count: usize),
+    67 => MakeDict(count: usize),
+    68 => Drop,
+    69 => Sep,
+    70 => Clone,
+    71 => Dup,
+    72 => Rot2,
+    73 => UnaryOp(op: &'static str),
+    74 => BinaryOp(op: &'static str),
+    75 => MakeIter,
+    76 => IterNext(count: usize),
+    77 => Catch,
+}
+
+impl Bytecode for Parselet {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        self.name.encode(enc)?;
+        self.consuming.encode(enc)?;
+        self.severity.encode(enc)?;
+        self.memo.encode(enc)?;
+        self.signature.encode(enc)?;
+        self.args_catchall.encode(enc)?;
+        self.nargs_catchall.encode(enc)?;
+        self.locals.encode(enc)?;
+        self.begin.encode(enc)?;
+        self.end.encode(enc)?;
+        self.body.encode(enc)
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let name = String::decode(dec)?;
+        let consuming = Option::<bool>::decode(dec)?;
+        let severity = u8::decode(dec)?;
+        let memo = bool::decode(dec)?;
+        let signature = Vec::<(String, Option<usize>)>::decode(dec)?;
+        let args_catchall = Option::<usize>::decode(dec)?;
+        let nargs_catchall = Option::<usize>::decode(dec)?;
+        let locals = usize::decode(dec)?;
+
+        if signature.len() > locals
+            || [args_catchall, nargs_catchall]
+                .iter()
+                .any(|addr| matches!(addr, Some(addr) if *addr >= locals))
+        {
+            return Err(format!("Invalid signature of parselet '{}'", name));
+        }
+
+        let mut parselet = Parselet::new(
+            Some(name),
+            consuming,
+            severity,
+            signature,
+            locals,
+            Vec::<Op>::decode(dec)?,
+            Vec::<Op>::decode(dec)?,
+            Vec::<Op>::decode(dec)?,
+        );
+
+        parselet.memo = memo;
+        parselet.args_catchall = args_catchall;
+        parselet.nargs_catchall = nargs_catchall;
+        Ok(parselet)
+    }
+}
+
+impl Bytecode for RefValue {
+    fn encode(&self, enc: &mut Encoder) -> Result<(), String> {
+        let value = self.borrow();
+
+        match &*value {
+            Value::Void => 0u8.encode(enc),
+            Value::Null => 1u8.encode(enc),
+            Value::True => 2u8.encode(enc),
+            Value::False => 3u8.encode(enc),
+            Value::Int(int) => {
+                4u8.encode(enc)?;
+                int.to_signed_bytes_le().encode(enc)
+            }
+            Value::Float(float) => {
+                5u8.encode(enc)?;
+                float.to_bits().encode(enc)
+            }
+            Value::Object(object) => {
+                if let Some(string) = value.object::<Str>() {
+                    6u8.encode(enc)?;
+                    string.as_str().to_string().encode(enc)
+                } else if let Some(list) = value.object::<List>() {
+                    7u8.encode(enc)?;
+                    list.to_vec().encode(enc)
+                } else if let Some(dict) = value.object::<Dict>() {
+                    8u8.encode(enc)?;
+                    dict.iter()
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect::<Vec<_>>()
+                        .encode(enc)
+                } else if let Some(parselet) = value.object::<ParseletRef>() {
+                    // Parselets are written once, and referred to by their index afterwards
+                    9u8.encode(enc)?;
+
+                    let id = parselet.id();
+                    if let Some(index) = enc.parselets.get(&id).copied() {
+                        return index.encode(enc);
+                    }
+
+                    let index = enc.parselets.len();
+                    enc.parselets.insert(id, index);
+                    index.encode(enc)?;
+                    parselet.0.borrow().encode(enc)
+                } else if let Some(token) = value.object::<Token>() {
+                    10u8.encode(enc)?;
+                    token.encode(enc)
+                } else if let Some(builtin) = value.object::<BuiltinRef>() {
+                    11u8.encode(enc)?;
+                    builtin.0.name.to_string().encode(enc)
+                } else {
+                    Err(format!("Can't serialize value of type '{}'", object.name()))
+                }
+            }
+        }
+    }
+
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(match u8::decode(dec)? {
+            0 => Value::Void.into(),
+            1 => Value::Null.into(),
+            2 => Value::True.into(),
+            3 => Value::False.into(),
+            4 => BigInt::from_signed_bytes_le(&Vec::<u8>::decode(dec)?).into(),
+            5 => f64::from_bits(u64::decode(dec)?).into(),
+            6 => String::decode(dec)?.into(),
+            tag @ (7 | 8) => {
+                if dec.depth == MAX_DEPTH {
+                    return Err("Values nested too deeply in bytecode".to_string());
+                }
+
+                dec.depth += 1;
+
+                let value = if tag == 7 {
+                    let mut list = List::new();
+                    list.extend(Vec::<RefValue>::decode(dec)?);
+                    list.into()
+                } else {
+                    let mut dict = Dict::new();
+                    dict.extend(Vec::<(String, RefValue)>::decode(dec)?);
+                    dict.into()
+                };
+
+                dec.depth -= 1;
+                value
+            }
+            9 => {
+                let index = usize::decode(dec)?;
+
+                if index == dec.parselets.len() {
+                    let parselet = ParseletRef(Rc::new(Parselet::decode(dec)?.into()));
+                    dec.parselets.push(parselet);
+                } else if index > dec.parselets.len() {
+                    return Err(format!("Invalid parselet {} in bytecode", index));
+                }
+
+                RefValue::from(Box::new(dec.parselets[index].clone()) as crate::value::BoxedObject)
+            }
+            10 => Token::decode(dec)?.into(),
+            11 => {
+                let name = String::decode(dec)?;
+
+                Builtin::get(&name)
+                    .ok_or(format!("Unknown builtin '{}' in bytecode", name))?
+                    .into()
+            }
+            tag => return Err(format!("Invalid value {} in bytecode", tag)),
+        })
+    }
+}
+
+#[test]
+fn test_ccl_ranges() {
+    use charclass::charclass;
+
+    for ccl in [
+        CharClass::new(),
+        CharClass::new().negate(),
+        charclass!['a' => 'z'],
+        charclass!['\0' => '\u{ff}'],
+        charclass!['0' => '9', 'A' => 'F', 'a' => 'f'],
+        charclass!['\u{d000}' => '\u{10ffff}'],
+        charclass!['\u{10fffe}' => '\u{10ffff}', '\0' => '\0'],
+    ] {
+        let mut copy = CharClass::new();
+
+        for (start, end) in ccl_ranges(&ccl) {
+            copy.add(start..=end);
+        }
+
+        assert_eq!(copy.len(), ccl.len(), "{:?} != {:?}", copy, ccl);
+        assert_eq!(format!("{:?}", copy), format!("{:?}", ccl));
+    }
+}
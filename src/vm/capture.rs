@@ -21,7 +21,7 @@ impl Capture {
         match self {
             Capture::Empty => crate::value!(void),
             Capture::Range(range, alias, severity) => {
-                let value = RefValue::from(reader.get(range));
+                let value = RefValue::from(reader.get(range).into_owned());
                 *self = Capture::Value(value.clone(), alias.clone(), *severity);
                 value
             }
@@ -123,7 +123,8 @@ impl<'runtime, 'program, 'reader, 'parselet> Context<'runtime, 'program, 'reader
             Some(RefValue::from(
                 self.runtime
                     .reader
-                    .get(&self.runtime.reader.capture_from(&self.reader_start)),
+                    .get(&self.runtime.reader.capture_from(&self.reader_start))
+                    .into_owned(),
             ))
         // Any other index.
         } else {
@@ -274,7 +275,7 @@ impl<'runtime, 'program, 'reader, 'parselet> Context<'runtime, 'program, 'reader
                         dict.clear();
                     }
 
-                    let value = RefValue::from(self.runtime.reader.get(&range));
+                    let value = RefValue::from(self.runtime.reader.get(&range).into_owned());
 
                     if let Some(alias) = alias {
                         dict.insert(alias, value);
@@ -1,16 +1,22 @@
 //! Tokay virtual machine
 
+mod bytecode;
 mod capture;
 mod context;
 mod op;
+mod profile;
 mod program;
 mod runtime;
+mod session;
 
+pub use bytecode::BYTECODE_MAGIC;
 pub use capture::*;
 pub use context::*;
 pub use op::*;
+pub use profile::*;
 pub use program::*;
 pub use runtime::*;
+pub use session::*;
 
 use crate::error::Error;
 use crate::value::{RefValue, Value};
@@ -2,7 +2,8 @@ use super::*;
 use crate::error::Error;
 use crate::reader::Offset;
 use crate::value;
-use crate::value::{Dict, List, Object, Str, Value};
+use crate::value::token::read_line_break;
+use crate::value::{Caught, Dict, Iter, List, Object, RefValue, Str, Value};
 use std::io;
 use std::io::prelude::*;
 use std::rc::Rc;
@@ -28,6 +29,8 @@ pub enum Op {
     Close,           // Close frame
     Collect(u8, u8), // Collect stack values from current frame
     Fuse(usize),     // Set frame fuse to forward address
+    Try(usize),      // Start new frame catching errors at forward address
+    Cut,             // Cut input at current position, forbidding any backtracking before it
 
     // Loop frames
     Loop(usize), // Loop frame
@@ -60,6 +63,8 @@ pub enum Op {
     LoadExit,              // Exit with errorcode
     Exit,                  // Exit with 0
     Error(Option<String>), // Error with optional error message (otherwise its expected on stack)
+    Recover,               // Record caught error and skip input behind sync string from stack
+    Catch,                 // Reset reader to frame start and push caught error as value
 
     // Call
     CallOrCopy,          // Load and eventually call stack element without parameters
@@ -101,6 +106,8 @@ pub enum Op {
     MakeAlias,       // Make key-value-Capture from last two stack items
     MakeList(usize), // Make a List from specified amount of items on stack
     MakeDict(usize), // Make a Dict from specified amount of key-value-pairs on the stack
+    MakeIter,        // Make an Iter from TOS
+    IterNext(usize), // Push next item (1) or key and item (2) of Iter at TOS, followed by true, or false when exhausted
 
     // Operations
     Drop,  // drop TOS
@@ -134,6 +141,7 @@ impl Op {
         #[derive(Debug)]
         struct Frame {
             fuse: Option<usize>,  // fuse
+            catch: Option<usize>, // catch address for errors
             capture_start: usize, // capture start
             reader_start: Offset, // reader start
         }
@@ -143,6 +151,7 @@ impl Op {
             fn new(context: &Context) -> Frame {
                 Frame {
                     fuse: None,
+                    catch: None,
                     capture_start: context.runtime.stack.len(),
                     reader_start: context.runtime.reader.tell(),
                 }
@@ -156,6 +165,7 @@ impl Op {
         let mut loops: Vec<(usize, usize, usize)> = Vec::new(); // Loops
 
         let mut frame = Frame::new(context); // Main capture
+        let mut caught: Option<Error> = None; // Error caught by a Try frame
         let mut state = Ok(Accept::Next);
 
         while ip < ops.len() {
@@ -190,12 +200,17 @@ impl Op {
                 }
             }
 
+            // Exceeded execution limits end the run, regardless of any Try frames
+            if let Err(limit) = context.runtime.step() {
+                return Error::from(limit).into();
+            }
+
             // Execute instruction
             state = match op {
                 Op::Nop => Ok(Accept::Next),
 
                 Op::Offset(offset) => {
-                    context.source_offset = Some(**offset);
+                    context.source_offset = Some(offset.as_ref().clone());
                     Ok(Accept::Next)
                 }
 
@@ -221,7 +236,17 @@ impl Op {
 
                 Op::Reset => {
                     context.runtime.stack.truncate(frame.capture_start);
-                    context.runtime.reader.reset(frame.reader_start);
+
+                    if frame.reader_start.offset < context.runtime.reader.base() {
+                        Error::new(None, "Cannot backtrack before cut".to_string()).into()
+                    } else {
+                        context.runtime.reader.reset(frame.reader_start.clone());
+                        Ok(Accept::Next)
+                    }
+                }
+
+                Op::Cut => {
+                    context.runtime.cut();
                     Ok(Accept::Next)
                 }
 
@@ -243,6 +268,13 @@ impl Op {
                     Ok(Accept::Next)
                 }
 
+                Op::Try(catch) => {
+                    frames.push(frame);
+                    frame = Frame::new(context);
+                    frame.catch = Some(ip + *catch);
+                    Ok(Accept::Next)
+                }
+
                 // Loops
                 Op::Loop(size) => {
                     frames.push(frame);
@@ -389,10 +421,84 @@ impl Op {
 
                 Op::Error(msg) => {
                     if let Some(msg) = msg {
-                        Error::new(Some(frame.reader_start), msg.clone()).into()
+                        Error::new(Some(frame.reader_start.clone()), msg.clone()).into()
+                    } else {
+                        Error::new(Some(frame.reader_start.clone()), context.pop().to_string())
+                            .into()
+                    }
+                }
+
+                Op::Catch => {
+                    let mut error = caught.take().unwrap();
+                    error.patch_offset(frame.reader_start.clone());
+
+                    if frame.reader_start.offset < context.runtime.reader.base() {
+                        Error::new(None, "Cannot backtrack before cut".to_string()).into()
                     } else {
-                        Error::new(Some(frame.reader_start), context.pop().to_string()).into()
+                        context.runtime.reader.reset(frame.reader_start.clone());
+
+                        let offset = error.offset.as_ref().unwrap().offset + context.runtime.start;
+                        context.push(RefValue::from(Caught::new(error, offset)))
+                    }
+                }
+
+                Op::Recover => {
+                    let sync = context.pop();
+                    let mut error = caught.take().unwrap();
+                    error.patch_offset(frame.reader_start.clone());
+
+                    // Skip input up to and including the synchronization point, which is either
+                    // a token or parselet, a string, or the end of the line.
+                    loop {
+                        let end = context.runtime.reader.tell();
+
+                        if sync.is_callable(true) {
+                            match sync.call(context, 0, None) {
+                                Ok(_) => break,
+                                Err(Reject::Next) => context.runtime.reader.reset(end),
+                                Err(reject) => return Err(reject),
+                            }
+                        } else if sync.is_void() {
+                            if read_line_break(context.runtime.reader) {
+                                break;
+                            }
+                        } else {
+                            let reader = &mut context.runtime.reader;
+
+                            if sync
+                                .to_string()
+                                .chars()
+                                .all(|ch| reader.once(|c| c == ch).is_some())
+                            {
+                                break;
+                            }
+
+                            reader.reset(end);
+                        }
+
+                        if context.runtime.reader.next().is_none() {
+                            break;
+                        }
                     }
+
+                    // The error node is built like an AST node from ast()
+                    let offset = error.offset.clone().unwrap();
+                    let stop = context.runtime.reader.tell();
+                    let start = context.runtime.start;
+
+                    let mut node = Dict::new();
+                    node.insert("emit".to_string(), value!("error"));
+                    node.insert("value".to_string(), value!(error.message.clone()));
+                    node.insert("offset".to_string(), value!(offset.offset + start));
+                    node.insert("row".to_string(), value!(offset.row as usize));
+                    node.insert("col".to_string(), value!(offset.col as usize));
+                    node.insert("stop_offset".to_string(), value!(stop.offset + start));
+                    node.insert("stop_row".to_string(), value!(stop.row as usize));
+                    node.insert("stop_col".to_string(), value!(stop.col as usize));
+
+                    context.runtime.errors.push(error);
+
+                    Ok(Accept::Push(Capture::Value(RefValue::from(node), None, 5)))
                 }
 
                 // Calls
@@ -494,9 +600,19 @@ impl Op {
                     let attr = attr.borrow();
                     let value = context.pop();
 
-                    match value.create_method(attr.object::<Str>().unwrap().as_str()) {
+                    let attr = attr.object::<Str>().unwrap().as_str();
+
+                    match value.create_method(attr) {
                         Ok(value) => context.push(value),
-                        Err(err) => err.into(),
+                        Err(err) => {
+                            // Objects may provide attributes which are not methods, e.g. err.message
+                            let attr = value.borrow().attr(attr);
+
+                            match attr {
+                                Some(attr) => context.push(attr),
+                                None => err.into(),
+                            }
+                        }
                     }
                 }
 
@@ -650,6 +766,33 @@ impl Op {
                     context.push(RefValue::from(dict))
                 }
 
+                Op::MakeIter => {
+                    let value = context.pop();
+                    context.push(RefValue::from(Iter::new(value)?))
+                }
+
+                Op::IterNext(count) => {
+                    let iter = context.pop();
+                    let mut iter = iter.borrow_mut();
+                    let iter = iter.object_mut::<Iter>().unwrap();
+
+                    let next = if *count == 2 {
+                        iter.next_pair().map(|(key, item)| vec![key, item])
+                    } else {
+                        iter.next_item().map(|item| vec![item])
+                    };
+
+                    if let Some(next) = next {
+                        for value in next {
+                            context.runtime.stack.push(Capture::Value(value, None, 10));
+                        }
+
+                        context.push(value!(true))
+                    } else {
+                        context.push(value!(false))
+                    }
+                }
+
                 // Operations
                 Op::Drop => {
                     context.pop();
@@ -707,6 +850,20 @@ impl Op {
                 context.debug(&format!("ip = {} state = {:?}", ip, state));
             }
 
+            // A soft reject which would backtrack before a cut becomes an error
+            if let (Err(Reject::Next), base @ 1..) = (&state, context.runtime.reader.base()) {
+                for current in std::iter::once(&frame).chain(frames.iter().rev()) {
+                    if current.reader_start.offset < base {
+                        state = Err(context.runtime.cut_error().into());
+                        break;
+                    }
+
+                    if matches!(current.fuse, Some(fuse) if fuse > ip) {
+                        break;
+                    }
+                }
+            }
+
             match state {
                 Ok(Accept::Hold) => {}
                 Ok(Accept::Next) => ip += 1,
@@ -715,9 +872,31 @@ impl Op {
                     state = Ok(Accept::Next);
                     ip += 1;
                 }
+                // Errors are caught by the nearest Try frame, where reading continues
+                Err(Reject::Error(ref error))
+                    if error.limit.is_none()
+                        && (frame.catch.is_some()
+                            || frames.iter().any(|frame| frame.catch.is_some())) =>
+                {
+                    while frame.catch.is_none() {
+                        frame = frames.pop().unwrap();
+                    }
+
+                    // Discard loops inside the catching frame
+                    loops.retain(|current| current.0 <= frames.len());
+
+                    context.runtime.stack.truncate(frame.capture_start);
+                    ip = frame.catch.take().unwrap();
+
+                    if let Err(Reject::Error(error)) = state {
+                        caught = Some(*error);
+                    }
+
+                    state = Ok(Accept::Next);
+                }
                 Err(Reject::Next) if frames.len() > 0 => loop {
                     context.runtime.stack.truncate(frame.capture_start);
-                    context.runtime.reader.reset(frame.reader_start);
+                    context.runtime.reader.reset(frame.reader_start.clone());
 
                     if let Some(fuse) = frame.fuse {
                         if fuse > ip {
@@ -0,0 +1,199 @@
+//! Per-parselet profiling of a program run.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Profiling statistics of one parselet.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileEntry {
+    pub name: String,        // Parselet's name
+    pub calls: usize,        // Total number of calls, including memo hits
+    pub memo_hits: usize,    // Calls answered from the memo table
+    pub memo_misses: usize,  // Calls not found in the memo table
+    pub growths: usize,      // Left-recursion growth iterations which consumed further input
+    pub time: Duration,      // Time spent inside the parselet, including called parselets
+    pub self_time: Duration, // Time spent inside the parselet only
+    pub consumed: usize,     // Bytes consumed by accepted calls
+}
+
+// Frame of a running parselet on the profiler's stack
+#[derive(Debug)]
+struct ProfileFrame {
+    id: usize,          // Parselet id
+    start: Instant,     // Time the parselet was entered
+    children: Duration, // Time spent in called parselets
+}
+
+/** Profiler collecting call statistics and folded stacks of parselets.
+
+The profiler is enabled by setting `Runtime::profile`. It counts calls, memo hits and misses,
+left-recursion growth iterations, time spent and bytes consumed per parselet. Self-times are
+also collected per call stack, to be written as folded stacks for flamegraph tooling. */
+#[derive(Debug, Default)]
+pub struct Profile {
+    entries: HashMap<usize, ProfileEntry>, // Statistics by parselet id
+    stack: Vec<ProfileFrame>,              // Currently running parselets
+    stacks: HashMap<Vec<usize>, Duration>, // Self-time by call stack of parselet ids
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Get entry of parselet id, creating it with its name on first use
+    fn entry(&mut self, id: usize, name: &str) -> &mut ProfileEntry {
+        self.entries.entry(id).or_insert_with(|| ProfileEntry {
+            name: name.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Count a call of a parselet, and whether it was answered by the memo table.
+    pub(crate) fn call(&mut self, id: usize, name: &str, memo_hit: Option<bool>) {
+        let entry = self.entry(id, name);
+        entry.calls += 1;
+
+        match memo_hit {
+            Some(true) => entry.memo_hits += 1,
+            Some(false) => entry.memo_misses += 1,
+            None => {}
+        }
+    }
+
+    /// Count a left-recursion growth iteration of a parselet.
+    pub(crate) fn grow(&mut self, id: usize) {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.growths += 1;
+        }
+    }
+
+    /// Enter execution of a parselet.
+    pub(crate) fn enter(&mut self, id: usize) {
+        self.stack.push(ProfileFrame {
+            id,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    /// Leave execution of a parselet, which consumed `consumed` bytes.
+    pub(crate) fn leave(&mut self, consumed: usize) {
+        let frame = self.stack.pop().unwrap();
+        let elapsed = frame.start.elapsed();
+        let self_time = elapsed.saturating_sub(frame.children);
+
+        // Self-time is recorded for the entire call stack
+        let mut path: Vec<usize> = self.stack.iter().map(|frame| frame.id).collect();
+        path.push(frame.id);
+        *self.stacks.entry(path).or_default() += self_time;
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += elapsed;
+        }
+
+        // Recursive calls are already covered by the outermost call's time
+        let recursive = self.stack.iter().any(|parent| parent.id == frame.id);
+
+        if let Some(entry) = self.entries.get_mut(&frame.id) {
+            if !recursive {
+                entry.time += elapsed;
+            }
+
+            entry.self_time += self_time;
+            entry.consumed += consumed;
+        }
+    }
+
+    /// Returns the collected statistics, sorted by time spent in descending order.
+    pub fn entries(&self) -> Vec<&ProfileEntry> {
+        let mut entries: Vec<&ProfileEntry> = self.entries.values().collect();
+        entries.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.name.cmp(&b.name)));
+        entries
+    }
+
+    /// Returns a summary table of the collected statistics.
+    pub fn summary(&self) -> String {
+        let mut ret = format!(
+            "{:<24} {:>10} {:>10} {:>10} {:>8} {:>12} {:>12} {:>12}\n",
+            "parselet", "calls", "memo hits", "misses", "growths", "time ms", "self ms", "bytes"
+        );
+
+        for entry in self.entries() {
+            ret.push_str(&format!(
+                "{:<24} {:>10} {:>10} {:>10} {:>8} {:>12.3} {:>12.3} {:>12}\n",
+                entry.name,
+                entry.calls,
+                entry.memo_hits,
+                entry.memo_misses,
+                entry.growths,
+                entry.time.as_secs_f64() * 1000.0,
+                entry.self_time.as_secs_f64() * 1000.0,
+                entry.consumed
+            ));
+        }
+
+        ret
+    }
+
+    /** Returns the collected self-times as folded stacks.
+
+    Every line holds a call stack of parselet names separated by `;`, followed by the
+    self-time in microseconds, as consumed by flamegraph tools like `flamegraph.pl`. */
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(path, time)| {
+                let names: Vec<&str> = path
+                    .iter()
+                    .map(|id| self.entries[id].name.as_str())
+                    .collect();
+
+                format!("{} {}", names.join(";"), time.as_micros())
+            })
+            .collect();
+
+        lines.sort();
+        lines.join("\n") + "\n"
+    }
+}
+
+#[test]
+fn test_profile() {
+    use crate::compiler::Compiler;
+    use crate::reader::Reader;
+    use crate::vm::Runtime;
+    use std::io::Cursor;
+
+    let mut compiler = Compiler::new(true);
+    compiler
+        .compile_from_str("Sum : @{ Sum '+' Int | Int }\nItem : @{ Sum ';' | Sum }\nItem")
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let mut reader = Reader::new(Box::new(Cursor::new("1+2+3")));
+    let mut runtime = Runtime::new(&program, &mut reader);
+    runtime.profile = Some(Profile::new());
+
+    program.run(&mut runtime).unwrap();
+
+    let profile = runtime.profile.unwrap();
+    let entries = profile.entries();
+    let entry = |name| *entries.iter().find(|entry| entry.name == name).unwrap();
+
+    // Item is called once, and consumes the entire input
+    assert_eq!(entry("Item").calls, 1);
+    assert_eq!(entry("Item").consumed, 5);
+
+    // Sum grows three times, and its second call is taken from the memo table
+    let sum = entry("Sum");
+    assert_eq!(sum.growths, 3);
+    assert_eq!(sum.memo_hits, sum.calls - sum.memo_misses);
+    assert!(sum.memo_hits >= 1);
+
+    // Folded stacks are rooted in __main__
+    let folded = profile.folded();
+    assert!(folded.lines().all(|line| line.starts_with("__main__")));
+    assert!(folded.contains("__main__;Item;Sum "));
+}
@@ -36,6 +36,24 @@ impl Program {
         self.main.as_ref().unwrap()
     }
 
+    /// Serializes the program into versioned bytecode, which is loaded by Program::from_bytecode().
+    pub fn to_bytecode(&self) -> Result<Vec<u8>, Error> {
+        bytecode::encode(&self.statics).map_err(|msg| Error::new(None, msg))
+    }
+
+    /// Loads a program from bytecode produced by Program::to_bytecode(), without any compiler.
+    pub fn from_bytecode(bytes: &[u8]) -> Result<Self, Error> {
+        match bytecode::decode(bytes) {
+            Ok(statics) => Ok(Self::new(statics)),
+            Err(msg) => Err(Error::new(None, msg)),
+        }
+    }
+
+    /// Checks whether bytes are bytecode.
+    pub fn is_bytecode(bytes: &[u8]) -> bool {
+        bytes.starts_with(BYTECODE_MAGIC)
+    }
+
     pub fn dump(&self) {
         for i in 0..self.statics.len() {
             println!("{} => {:#?}", i, self.statics[i]);
@@ -44,11 +62,19 @@ impl Program {
 
     pub fn run(&self, runtime: &mut Runtime) -> Result<Option<RefValue>, Error> {
         if let Some(main) = &self.main {
-            match main
+            let ret = main
                 .0
                 .borrow()
-                .run(runtime, runtime.stack.len(), None, true, 0)
-            {
+                .run(runtime, runtime.stack.len(), None, true, 0);
+
+            // Input which couldn't be read is reported as error
+            if let Some(error) = runtime.reader.take_error() {
+                if !matches!(ret, Err(Reject::Error(_))) {
+                    return Err(error);
+                }
+            }
+
+            match ret {
                 Ok(Accept::Push(Capture::Value(value, ..))) => {
                     if value.is_void() {
                         Ok(None)
@@ -58,6 +84,9 @@ impl Program {
                 }
                 Ok(_) => Ok(None),
                 Err(Reject::Error(error)) => Err(*error),
+                Err(Reject::Next) if runtime.failure.is_some() => {
+                    Err(runtime.failure.take().unwrap().into_error())
+                }
                 Err(other) => Err(Error::new(None, format!("Runtime error {:?}", other))),
             }
         } else {
@@ -86,7 +115,9 @@ impl Program {
         if filename == "-" {
             self.run_from_reader(Reader::new(Box::new(BufReader::new(io::stdin()))))
         } else if let Ok(file) = File::open(filename) {
-            self.run_from_reader(Reader::new(Box::new(BufReader::new(file))))
+            let mut reader = Reader::new(Box::new(BufReader::new(file)));
+            reader.set_source(filename);
+            self.run_from_reader(reader)
         } else {
             Err(Error::new(
                 None,
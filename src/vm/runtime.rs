@@ -1,10 +1,119 @@
 //! Holds overall required information for VM execution.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use super::*;
-use crate::reader::{Offset, Reader};
+use crate::error::Error;
+use crate::reader::{Indent, Offset, Reader};
 use crate::value::RefValue;
+use std::rc::Rc;
+
+/// Memoization key of reader offset, indentation and parselet id.
+pub(crate) type MemoKey = (usize, Option<Rc<Indent>>, usize);
+
+/// Default maximum recursion depth of parselet calls.
+pub const MAX_DEPTH: usize = 10_000;
+
+// Number of executed operations between checks of the deadline
+const DEADLINE_INTERVAL: usize = 1024;
+
+/** Execution limit which ended a run.
+
+Runs ending by an exceeded limit fail with an error holding the limit in `Error::limit`. Such errors
+are never caught by `recover()`. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Depth(usize), // Maximum recursion depth of parselet calls exceeded
+    Steps(usize), // Maximum number of executed operations exceeded
+    Timeout,      // Deadline passed
+    Stack(usize), // Maximum number of stack items exceeded
+    Memo(usize),  // Maximum number of memo entries exceeded
+    Cancelled,    // Run was cancelled by its Cancel handle
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Depth(max) => write!(f, "Maximum recursion depth of {} exceeded", max),
+            Limit::Steps(max) => write!(f, "Maximum of {} executed operations exceeded", max),
+            Limit::Timeout => write!(f, "Timeout exceeded"),
+            Limit::Stack(max) => write!(f, "Maximum stack size of {} items exceeded", max),
+            Limit::Memo(max) => write!(f, "Maximum memo size of {} entries exceeded", max),
+            Limit::Cancelled => write!(f, "Run cancelled"),
+        }
+    }
+}
+
+/** Handle to cancel a run from another thread.
+
+The handle is cloned into `Limits::cancel` of a runtime, and the run ends with a `Limit::Cancelled`
+error as soon as `Cancel::cancel()` was called on any clone. */
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all runs using this handle.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks whether the handle was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Optional execution limits of a runtime; No limits are set by default.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub steps: Option<usize>,      // maximum number of executed operations
+    pub deadline: Option<Instant>, // wall-clock deadline
+    pub stack: Option<usize>,      // maximum number of stack items
+    pub memo: Option<usize>,       // maximum number of memo entries
+    pub cancel: Option<Cancel>,    // cancellation handle
+}
+
+/** Memoization policy of a runtime.
+
+Results of consuming parselets are memoized by their reader offset, so that a parselet called again
+at the same position doesn't need to run again. Left-recursive parselets always rely on the memo
+table, regardless of the policy. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Memoization {
+    Full,           // Memoize all consuming parselets, except those flagged otherwise
+    Bounded(usize), // Like Full, but evict the oldest entries beyond the given number of entries
+    Linear,         // Only memoize left-recursive parselets
+}
+
+/** Farthest failure of a run.
+
+Records the farthest reader offset where input was rejected, and the tokens and parselets which
+were expected there. It is used to report parse errors which weren't caught by the program. */
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub offset: Offset,        // farthest offset where input was rejected
+    pub expected: Vec<String>, // tokens and parselet names expected at offset
+}
+
+impl Failure {
+    /// Turns the failure into an error at its offset.
+    pub fn into_error(self) -> Error {
+        let msg = match self.expected.len() {
+            0 => "Parse error, unexpected input".to_string(),
+            1 => format!("Expecting {}", self.expected[0]),
+            _ => format!("Expecting one of: {}", self.expected.join(", ")),
+        };
+
+        Error::new(Some(self.offset), msg)
+    }
+}
 
 /** Merges a program and a reader into one container.
 
@@ -15,8 +124,19 @@ pub struct Runtime<'program, 'reader> {
     pub(crate) reader: &'reader mut Reader, // reader to read from
     pub(crate) start: usize,                // absolute start offset in relation to reader
 
-    pub(crate) memo: HashMap<(usize, usize), (Offset, Result<Accept, Reject>)>, // memoization table
-    pub(crate) stack: Vec<Capture>,                                             // value stack
+    pub(crate) memo: HashMap<MemoKey, (Offset, Result<Accept, Reject>)>, // memoization table
+    pub(crate) memo_order: VecDeque<MemoKey>, // memo keys in insertion order, when bounded
+    pub memoization: Memoization,             // memoization policy
+    pub(crate) stack: Vec<Capture>,           // value stack
+
+    pub(crate) results: Option<Vec<RefValue>>, // immediately delivered main results
+    pub(crate) resume: bool,                   // main resumes a suspended run
+    pub(crate) failure: Option<Failure>,       // farthest failure within current main iteration
+    pub errors: Vec<Error>,                    // errors recovered from by Recover
+    pub profile: Option<Profile>,              // profiler, when profiling is enabled
+    pub max_depth: Option<usize>,              // maximum recursion depth of parselet calls
+    pub limits: Limits,                        // execution limits
+    pub(crate) steps: usize,                   // number of executed operations
 
     pub debug: u8, // Debug level
 }
@@ -28,7 +148,17 @@ impl<'program, 'reader> Runtime<'program, 'reader> {
             reader,
             start: 0,
             memo: HashMap::new(),
+            memo_order: VecDeque::new(),
+            memoization: Memoization::Full,
             stack: Vec::new(),
+            results: None,
+            resume: false,
+            failure: None,
+            errors: Vec::new(),
+            profile: None,
+            max_depth: Some(MAX_DEPTH),
+            limits: Limits::default(),
+            steps: 0,
             debug: if let Ok(level) = std::env::var("TOKAY_DEBUG") {
                 level.parse::<u8>().unwrap_or_default()
             } else {
@@ -37,6 +167,132 @@ impl<'program, 'reader> Runtime<'program, 'reader> {
         }
     }
 
+    /// Records that `expected` was rejected at reader offset `offset`.
+    pub(crate) fn reject_at(&mut self, offset: &Offset, expected: impl FnOnce() -> String) {
+        match &mut self.failure {
+            Some(failure) if failure.offset.offset > offset.offset => {}
+            Some(failure) if failure.offset.offset == offset.offset => {
+                let expected = expected();
+
+                if !failure.expected.contains(&expected) {
+                    failure.expected.push(expected);
+                }
+            }
+            failure => {
+                *failure = Some(Failure {
+                    offset: offset.clone(),
+                    expected: vec![expected()],
+                })
+            }
+        }
+    }
+
+    /// Counts an executed operation, and checks the execution limits.
+    pub(crate) fn step(&mut self) -> Result<(), Limit> {
+        self.steps += 1;
+
+        if let Some(max) = self.limits.steps {
+            if self.steps > max {
+                return Err(Limit::Steps(max));
+            }
+        }
+
+        if let Some(max) = self.limits.stack {
+            if self.stack.len() > max {
+                return Err(Limit::Stack(max));
+            }
+        }
+
+        if let Some(max) = self.limits.memo {
+            if self.memo.len() > max {
+                return Err(Limit::Memo(max));
+            }
+        }
+
+        if let Some(cancel) = &self.limits.cancel {
+            if cancel.is_cancelled() {
+                return Err(Limit::Cancelled);
+            }
+        }
+
+        // Reading the clock is expensive, so the deadline is only checked in intervals
+        if let Some(deadline) = self.limits.deadline {
+            if self.steps % DEADLINE_INTERVAL == 1 && Instant::now() >= deadline {
+                return Err(Limit::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Memoizes the result of a parselet call.
+    pub(crate) fn memoize(
+        &mut self,
+        key: MemoKey,
+        reader_end: Offset,
+        result: Result<Accept, Reject>,
+    ) {
+        // A key memoized again keeps only its latest position in the eviction order
+        if self
+            .memo
+            .insert(key.clone(), (reader_end, result))
+            .is_some()
+        {
+            self.memo_order.retain(|order| *order != key);
+        }
+
+        self.bound_memo(key);
+    }
+
+    /// Makes a memo entry subject to eviction, and evicts the oldest entries beyond the bound.
+    pub(crate) fn bound_memo(&mut self, key: MemoKey) {
+        if let Memoization::Bounded(limit) = self.memoization {
+            self.memo_order.push_back(key);
+
+            while self.memo_order.len() > limit {
+                let key = self.memo_order.pop_front().unwrap();
+                self.memo.remove(&key);
+            }
+        }
+    }
+
+    /// Clears the memo table.
+    pub(crate) fn clear_memo(&mut self) {
+        self.memo.clear();
+        self.memo_order.clear();
+    }
+
+    /** Cuts the input at the current reader offset.
+
+    Captured ranges on the stack are turned into values, the reader's buffer is drained and memo
+    entries before the current offset are dropped. Input before the cut can't be read again. */
+    pub(crate) fn cut(&mut self) {
+        for capture in self.stack.iter_mut() {
+            if let Capture::Range(..) = capture {
+                capture.extract(self.reader);
+            }
+        }
+
+        let offset = self.reader.tell().offset;
+
+        self.reader.cut();
+        self.memo.retain(|key, _| key.0 >= offset);
+        self.memo_order.retain(|key| key.0 >= offset);
+        self.failure = None;
+    }
+
+    /// Turns a failure which would backtrack before a cut into an error.
+    pub(crate) fn cut_error(&mut self) -> Error {
+        match self.failure.take() {
+            Some(failure) => failure.into_error(),
+            None => Failure {
+                offset: self.reader.tell(),
+                expected: Vec::new(),
+            }
+            .into_error(),
+        }
+    }
+
     pub fn load_stack(&mut self, stack: Vec<RefValue>) {
         for item in stack {
             self.stack.push(Capture::Value(item, None, 0));
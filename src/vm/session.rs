@@ -0,0 +1,140 @@
+//! Push-based, incremental execution of a program.
+
+use super::*;
+use crate::error::Error;
+use crate::reader::Reader;
+use crate::value::RefValue;
+
+/** Sessions run a program on input which is fed incrementally.
+
+Input chunks are fed by Session::feed() as they arrive, e.g. from an event loop or socket callback.
+Any results of the main parselet which are complete are returned immediately. Session::finish()
+marks the end of input, runs the program to its end and returns any remaining results.
+
+An iteration of main which runs out of input is repeated from its start when further input is
+fed. Side effects of the suspended iteration, like output or assignments to global variables,
+are therefore executed again. */
+pub struct Session<'program> {
+    program: &'program Program, // program to execute
+    reader: Reader,             // incremental reader
+    globals: Vec<RefValue>,     // globals held between runs
+    start: usize,               // absolute start offset in relation to reader
+    started: bool,              // begin has already been executed
+}
+
+impl<'program> Session<'program> {
+    pub fn new(program: &'program Program) -> Self {
+        Self {
+            program,
+            reader: Reader::new_incremental(),
+            globals: Vec::new(),
+            start: 0,
+            started: false,
+        }
+    }
+
+    /// Feed a chunk of input, and return any results which are complete.
+    pub fn feed(&mut self, input: &str) -> Result<Vec<RefValue>, Error> {
+        self.reader.feed(input);
+        self.run()
+    }
+
+    /// Mark the end of input, and return any remaining results.
+    pub fn finish(mut self) -> Result<Vec<RefValue>, Error> {
+        self.reader.finish();
+        self.run()
+    }
+
+    /// Internal function to run the program until the reader is starved or the input is finished.
+    fn run(&mut self) -> Result<Vec<RefValue>, Error> {
+        let mut runtime = Runtime::new(self.program, &mut self.reader);
+        runtime.start = self.start;
+        runtime.results = Some(Vec::new());
+        runtime.resume = self.started;
+        runtime.load_stack(std::mem::take(&mut self.globals));
+
+        let ret = self.program.run(&mut runtime);
+
+        let mut results = runtime.results.take().unwrap();
+        self.start = runtime.start;
+        self.started = true;
+        self.globals = runtime.save_stack();
+
+        // Any value returned by main other than the repeated results is delivered as well.
+        if let Some(value) = ret? {
+            results.push(value);
+        }
+
+        Ok(results)
+    }
+}
+
+impl Program {
+    /// Creates a new session to run the program on incrementally fed input.
+    pub fn session(&self) -> Session<'_> {
+        Session::new(self)
+    }
+}
+
+#[test]
+fn test_session() {
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str("begin \"begin\"\nend \"end\"\nInt")
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    let mut session = program.session();
+
+    // Results are delivered as soon as they are complete
+    assert_eq!(session.feed("12").unwrap(), vec![RefValue::from("begin")]);
+    assert_eq!(session.feed("34 5").unwrap(), vec![RefValue::from(1234)]);
+    assert!(session.feed("6").unwrap().is_empty());
+
+    // Finishing runs main to its end
+    assert_eq!(
+        session.finish().unwrap(),
+        vec![RefValue::from(56), RefValue::from("end")]
+    );
+}
+
+#[test]
+fn test_session_cut() {
+    let mut compiler = crate::Compiler::new(true);
+    compiler.compile_from_str("'a' cut 'b' 'c'").unwrap();
+    let program = compiler.finalize().unwrap();
+
+    // An iteration running out of input after a cut is repeated with further input
+    let mut session = program.session();
+
+    assert!(session.feed("a").unwrap().is_empty());
+    assert!(session.feed("b").unwrap().is_empty());
+    assert_eq!(
+        session.feed("ca").unwrap(),
+        vec![crate::value!(["a", "b", "c"])]
+    );
+
+    // Failures behind the cut are still reported as errors
+    assert_eq!(
+        session.feed("bx").unwrap_err().to_string(),
+        "Line 1, column 6: Expecting 'c'"
+    );
+}
+
+#[test]
+fn test_session_replay() {
+    let mut compiler = crate::Compiler::new(true);
+    compiler
+        .compile_from_str("begin count = 0\n'a' count += 1 'b' count")
+        .unwrap();
+    let program = compiler.finalize().unwrap();
+
+    // Side effects of an iteration are executed once when its input is complete...
+    let mut session = program.session();
+    assert_eq!(session.feed("ab").unwrap(), vec![RefValue::from(1)]);
+
+    // ...but again when the iteration is repeated with further input
+    let mut session = program.session();
+    assert!(session.feed("a").unwrap().is_empty());
+    assert_eq!(session.feed("b").unwrap(), vec![RefValue::from(2)]);
+}